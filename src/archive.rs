@@ -0,0 +1,84 @@
+use anyhow::{bail, Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Writes every file under `dir` to the archive `path`, a zip file if it
+/// ends in `.zip`, or a tar file if it ends in `.tar`, gzip compressed if in
+/// `.tar.gz` or `.tgz`
+///
+/// Entries are named by their path relative to `dir`, e.g.
+/// `logs-page-stats/host=.../data.parquet`, in sorted order. Returns the
+/// number of files written
+pub fn write_archive(dir: &Path, path: &Path) -> Result<usize> {
+    let mut files = Vec::new();
+    find_files(dir, &mut files)?;
+    files.sort();
+    let entries = files.iter().map(|file| {
+        let name = file
+            .strip_prefix(dir)
+            .expect("files are under dir")
+            .to_string_lossy()
+            // Archives separate directories with / on every platform
+            .replace(std::path::MAIN_SEPARATOR, "/");
+        (name, file.as_path())
+    });
+
+    let name = path.to_string_lossy();
+    let sink =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let sink = BufWriter::new(sink);
+    if name.ends_with(".zip") {
+        let mut zip = zip::ZipWriter::new(sink);
+        for (name, file) in entries {
+            zip.start_file(name, zip::write::SimpleFileOptions::default())?;
+            let mut source =
+                File::open(file).with_context(|| format!("failed to open {}", file.display()))?;
+            std::io::copy(&mut source, &mut zip)?;
+        }
+        zip.finish()?.flush()?;
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        let tar = write_tar(GzEncoder::new(sink, Compression::default()), entries)?;
+        tar.finish()?.flush()?;
+    } else if name.ends_with(".tar") {
+        write_tar(sink, entries)?.flush()?;
+    } else {
+        bail!(
+            "unknown archive format of {}, expected .zip, .tar, .tar.gz or .tgz",
+            path.display()
+        );
+    }
+    Ok(files.len())
+}
+
+/// Writes `entries` of names and files as a tar file to `sink`, returning
+/// the sink
+fn write_tar<'a, W: Write>(
+    sink: W,
+    entries: impl Iterator<Item = (String, &'a Path)>,
+) -> Result<W> {
+    let mut tar = tar::Builder::new(sink);
+    for (name, file) in entries {
+        tar.append_path_with_name(file, name)
+            .with_context(|| format!("failed to archive {}", file.display()))?;
+    }
+    Ok(tar.into_inner()?)
+}
+
+/// Appends the paths of the files under `dir` to `paths`
+fn find_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry.with_context(|| format!("failed to read {}", dir.display()))?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            find_files(&path, paths)?;
+        } else {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
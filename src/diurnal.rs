@@ -0,0 +1,81 @@
+use std::f64::consts::TAU;
+
+/// Microseconds in a day
+const DAY_US: f64 = 86_400e6;
+
+/// A day/night traffic pattern, where the density of entries over a day
+/// follows a sinusoid peaking at a given hour
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Diurnal {
+    peak_us: f64,
+    amplitude: f64,
+}
+
+impl Diurnal {
+    /// Creates a new [`Diurnal`] peaking at `peak_hour` UTC, e.g. 14.5 for
+    /// 14:30, where the density of entries ranges from `1 - amplitude` to
+    /// `1 + amplitude` times the average
+    ///
+    /// Returns an error unless `0 <= peak_hour < 24` and
+    /// `0 <= amplitude <= 1`
+    pub fn new(peak_hour: f64, amplitude: f64) -> Result<Self, String> {
+        if !(0. ..24.).contains(&peak_hour) {
+            return Err(format!(
+                "expected a peak hour in [0, 24), got {}",
+                peak_hour
+            ));
+        }
+        if !(0. ..=1.).contains(&amplitude) {
+            return Err(format!(
+                "expected an amplitude in [0, 1], got {}",
+                amplitude
+            ));
+        }
+        Ok(Self {
+            peak_us: peak_hour * 3600e6,
+            amplitude,
+        })
+    }
+
+    /// Returns the time of the entry that would be `offset_us` microseconds
+    /// after `start_us` if entries were evenly spaced, moving entries towards
+    /// the peak and away from the trough
+    ///
+    /// Entries arrive at a rate proportional to
+    /// `1 + amplitude * cos(2π (t - peak) / day)`, so the time `t` of an
+    /// entry solves `N(t - start) = offset` for the number of entries `N`
+    /// expected since `start`. The mapping is monotonic, so sorted times
+    /// remain sorted
+    pub(crate) fn warp(&self, start_us: i64, offset_us: i64) -> i64 {
+        let scale = self.amplitude * DAY_US / TAU;
+        let phase = |t: f64| TAU * (start_us as f64 + t - self.peak_us).rem_euclid(DAY_US) / DAY_US;
+        let at_start = phase(0.).sin();
+        let entries = |t: f64| t + scale * (phase(t).sin() - at_start);
+        let rate = |t: f64| 1. + self.amplitude * phase(t).cos();
+
+        // Entries are at most 2 * scale away from their even spacing
+        let target = offset_us as f64;
+        let (mut lo, mut hi) = (target - 2. * scale, target + 2. * scale);
+        let mut t = target;
+        // Newton's method, falling back to bisection when it leaves the
+        // bracket or the rate is 0 at the trough
+        for _ in 0..64 {
+            let error = entries(t) - target;
+            if error.abs() < 0.5 {
+                break;
+            }
+            if error > 0. {
+                hi = t;
+            } else {
+                lo = t;
+            }
+            let next = t - error / rate(t);
+            t = if next > lo && next < hi {
+                next
+            } else {
+                (lo + hi) / 2.
+            };
+        }
+        start_us + t.round() as i64
+    }
+}
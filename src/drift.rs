@@ -0,0 +1,121 @@
+use arrow::array::{ArrayRef, StringArray};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+
+/// How the schema of a file differs from the generated schema, see
+/// [`SchemaDrift`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Drift {
+    /// The generated schema
+    Unchanged,
+    /// Drops `client_addr`
+    DropClientAddr,
+    /// Adds a nullable `trace_id` column of 32 random hex digits
+    AddTraceId,
+    /// Moves `time` to the first column
+    TimeFirst,
+    /// Makes every top-level column nullable, without adding nulls
+    AllNullable,
+}
+
+impl Drift {
+    /// The drifts of consecutive files, which repeat from the start after
+    /// the last
+    pub const CYCLE: [Drift; 5] = [
+        Self::Unchanged,
+        Self::DropClientAddr,
+        Self::AddTraceId,
+        Self::TimeFirst,
+        Self::AllNullable,
+    ];
+
+    /// Returns a description of the drift, e.g. `drops client_addr`
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Unchanged => "unchanged",
+            Self::DropClientAddr => "drops client_addr",
+            Self::AddTraceId => "adds a nullable trace_id column",
+            Self::TimeFirst => "moves time to the first column",
+            Self::AllNullable => "makes every column nullable",
+        }
+    }
+
+    /// Returns `schema` with the drift, which is unchanged if the column it
+    /// drops or moves is missing, or the column it adds is already there
+    pub fn schema(self, schema: &Schema) -> SchemaRef {
+        let mut fields = schema.fields().to_vec();
+        match self {
+            Self::Unchanged => {}
+            Self::DropClientAddr => fields.retain(|field| field.name() != "client_addr"),
+            Self::AddTraceId => {
+                if schema.field_with_name("trace_id").is_err() {
+                    fields.push(Arc::new(Field::new("trace_id", DataType::Utf8, true)));
+                }
+            }
+            Self::TimeFirst => {
+                if let Ok(idx) = schema.index_of("time") {
+                    let time = fields.remove(idx);
+                    fields.insert(0, time);
+                }
+            }
+            Self::AllNullable => {
+                for field in &mut fields {
+                    *field = Arc::new(field.as_ref().clone().with_nullable(true));
+                }
+            }
+        }
+        Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()))
+    }
+
+    /// Returns `batch` with the drift, drawing `trace_id`s from `rng`
+    fn apply(self, batch: RecordBatch, rng: &mut StdRng) -> RecordBatch {
+        let schema = self.schema(batch.schema_ref());
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|field| match batch.column_by_name(field.name()) {
+                Some(column) => column.clone(),
+                None => {
+                    let trace_ids: StringArray = (0..batch.num_rows())
+                        .map(|_| Some(format!("{:032x}", rng.gen::<u128>())))
+                        .collect();
+                    Arc::new(trace_ids) as ArrayRef
+                }
+            })
+            .collect();
+        RecordBatch::try_new(schema, columns).expect("drift keeps the column types")
+    }
+}
+
+/// Deterministic differences between the schemas of the files of a
+/// dataset, for testing readers that merge them
+///
+/// The `n`th part file has the `n % 5`th of [`Drift::CYCLE`], so the first
+/// file has the generated schema, the second drops `client_addr`, and so on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaDrift {
+    seed: u64,
+}
+
+impl SchemaDrift {
+    /// Creates a new [`SchemaDrift`] whose added `trace_id`s are determined
+    /// by `seed`
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Returns the drift of part `part`
+    pub fn drift(&self, part: usize) -> Drift {
+        Drift::CYCLE[part % Drift::CYCLE.len()]
+    }
+
+    /// Returns a function applying the drift of part `part` to its batches
+    pub(crate) fn part(&self, part: usize) -> impl FnMut(RecordBatch) -> RecordBatch {
+        let drift = self.drift(part);
+        let mut rng = StdRng::seed_from_u64(self.seed ^ part as u64);
+        move |batch| drift.apply(batch, &mut rng)
+    }
+}
@@ -0,0 +1,57 @@
+use rand::Rng;
+use rand_distr::{Distribution, LogNormal};
+
+/// The 99th percentile of the standard normal distribution
+const Z_99: f64 = 2.326_347_874_040_841;
+
+/// A log-normal distribution of request durations, so most requests are
+/// fast with a long tail of slow ones
+#[derive(Debug, Clone)]
+pub struct Durations {
+    distribution: LogNormal<f64>,
+}
+
+impl Durations {
+    /// Creates a new [`Durations`] with the given mean and 99th percentile,
+    /// in milliseconds
+    ///
+    /// Returns an error unless `0 < mean_ms < p99_ms`, and as a log-normal
+    /// distribution's mean grows with its tail, `p99_ms` must be at most
+    /// about 15 times `mean_ms`
+    pub fn new(mean_ms: f64, p99_ms: f64) -> Result<Self, String> {
+        if !(mean_ms > 0. && p99_ms > mean_ms && p99_ms.is_finite()) {
+            return Err(format!(
+                "expected 0 < mean < p99, got mean {}ms and p99 {}ms",
+                mean_ms, p99_ms
+            ));
+        }
+        // Solves mean = exp(mu + sigma^2 / 2) and p99 = exp(mu + Z_99 * sigma)
+        // for the smaller of the two solutions of sigma
+        let discriminant = Z_99 * Z_99 - 2. * (p99_ms.ln() - mean_ms.ln());
+        if discriminant < 0. {
+            return Err(format!(
+                "p99 {}ms is too large for a mean of {}ms, it must be at most {:.1}ms",
+                p99_ms,
+                mean_ms,
+                mean_ms * (Z_99 * Z_99 / 2.).exp()
+            ));
+        }
+        let sigma = Z_99 - discriminant.sqrt();
+        let mu = mean_ms.ln() - sigma * sigma / 2.;
+        let distribution = LogNormal::new(mu, sigma).map_err(|e| e.to_string())?;
+        Ok(Self { distribution })
+    }
+
+    /// Returns a random duration in nanoseconds
+    pub(crate) fn sample<R: Rng>(&self, rng: &mut R) -> i64 {
+        let ms = self.distribution.sample(rng);
+        (ms * 1e6).min(i64::MAX as f64) as i64
+    }
+}
+
+impl Default for Durations {
+    /// A mean of 50ms and 99th percentile of 500ms
+    fn default() -> Self {
+        Self::new(50., 500.).unwrap()
+    }
+}
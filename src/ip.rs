@@ -0,0 +1,73 @@
+use rand::Rng;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// A publicly routed address block and the country it is allocated to
+struct Block<T> {
+    network: T,
+    prefix_len: u32,
+    country: &'static str,
+}
+
+const fn v4(network: [u8; 4], prefix_len: u32, country: &'static str) -> Block<Ipv4Addr> {
+    let [a, b, c, d] = network;
+    Block {
+        network: Ipv4Addr::new(a, b, c, d),
+        prefix_len,
+        country,
+    }
+}
+
+const fn v6(network: [u16; 2], prefix_len: u32, country: &'static str) -> Block<Ipv6Addr> {
+    let [a, b] = network;
+    Block {
+        network: Ipv6Addr::new(a, b, 0, 0, 0, 0, 0, 0),
+        prefix_len,
+        country,
+    }
+}
+
+/// A curated set of large cloud provider and ISP IPv4 allocations
+const V4_BLOCKS: &[Block<Ipv4Addr>] = &[
+    v4([3, 0, 0, 0], 9, "US"),
+    v4([34, 64, 0, 0], 10, "US"),
+    v4([73, 0, 0, 0], 8, "US"),
+    v4([99, 224, 0, 0], 11, "CA"),
+    v4([86, 0, 0, 0], 11, "GB"),
+    v4([79, 192, 0, 0], 10, "DE"),
+    v4([90, 0, 0, 0], 9, "FR"),
+    v4([126, 0, 0, 0], 8, "JP"),
+    v4([36, 96, 0, 0], 11, "CN"),
+    v4([122, 160, 0, 0], 12, "IN"),
+    v4([177, 0, 0, 0], 13, "BR"),
+    v4([1, 120, 0, 0], 14, "AU"),
+];
+
+/// A curated set of large cloud provider and ISP IPv6 allocations
+const V6_BLOCKS: &[Block<Ipv6Addr>] = &[
+    v6([0x2600, 0x1f00], 24, "US"),
+    v6([0x2001, 0x4860], 32, "US"),
+    v6([0x2601, 0], 20, "US"),
+    v6([0x2a02, 0x8100], 26, "DE"),
+    v6([0x2a01, 0x0e00], 26, "FR"),
+    v6([0x2a00, 0x23c0], 27, "GB"),
+    v6([0x240b, 0], 24, "JP"),
+    v6([0x240e, 0], 20, "CN"),
+    v6([0x2001, 0x8003], 32, "AU"),
+    v6([0x2804, 0], 16, "BR"),
+];
+
+/// Returns a random address from one of [`V4_BLOCKS`] or [`V6_BLOCKS`],
+/// along with the country of the block
+pub(crate) fn random_public<R: Rng>(rng: &mut R, v6: bool) -> (IpAddr, &'static str) {
+    if v6 {
+        let block = &V6_BLOCKS[rng.gen_range(0..V6_BLOCKS.len())];
+        let host = rng.gen::<u128>() >> block.prefix_len;
+        let addr = Ipv6Addr::from(u128::from(block.network) | host);
+        (addr.into(), block.country)
+    } else {
+        let block = &V4_BLOCKS[rng.gen_range(0..V4_BLOCKS.len())];
+        let host = rng.gen::<u32>() >> block.prefix_len;
+        let addr = Ipv4Addr::from(u32::from(block.network) | host);
+        (addr.into(), block.country)
+    }
+}
@@ -0,0 +1,1816 @@
+//! Generates random [`RecordBatch`]es with a schema approximating an access log
+
+use arrow::array::{
+    Array, ArrayRef, AsArray, Decimal128Builder, Int32Array, Int32Builder, Int64Builder,
+    ListBuilder, MapBuilder, StringBuilder, StringDictionaryBuilder, StructArray,
+    TimestampMicrosecondBuilder, TimestampNanosecondBuilder, UInt16Builder, UInt32Array,
+};
+use arrow::compute::take_record_batch;
+use arrow::datatypes::{
+    DataType, Field, FieldRef, Int32Type, Int64Type, Schema, SchemaRef, TimeUnit,
+    TimestampMicrosecondType, TimestampNanosecondType,
+};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use rand::distributions::uniform::SampleRange;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::net::Ipv6Addr;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[cfg(feature = "archive")]
+mod archive;
+mod diurnal;
+mod drift;
+mod duration;
+mod ip;
+mod partition;
+mod route;
+mod row;
+mod size;
+mod spike;
+mod stats;
+mod user_agent;
+mod weighted;
+mod writer;
+
+#[cfg(feature = "archive")]
+pub use archive::write_archive;
+pub use diurnal::Diurnal;
+pub use drift::{Drift, SchemaDrift};
+pub use duration::Durations;
+pub use partition::{
+    next_part, write_parquet_partitioned, write_parquet_rolling, write_success_marker, PartitionBy,
+};
+pub use route::Routes;
+pub use row::LogRow;
+pub use size::Sizes;
+pub use spike::{SpikeKind, SpikeWindow, Spikes};
+pub use stats::{ColumnRanges, DatasetStats, RowsSummary};
+pub use user_agent::{Category, UserAgents, DEFAULT_USER_AGENT_WEIGHTS};
+pub use weighted::Weighted;
+#[cfg(feature = "orc")]
+pub use writer::write_orc;
+#[cfg(feature = "async")]
+pub use writer::write_parquet_async;
+pub use writer::{
+    write_clf, write_csv, write_ipc, write_ndjson, write_parquet, write_parquet_flushing,
+};
+
+/// Configuration for a [`Generator`]
+///
+/// The default configuration reproduces the original fixed behaviour
+#[derive(Debug, Clone)]
+pub struct GeneratorConfig {
+    /// If set, every batch contains exactly this many rows, truncating or
+    /// padding the last container as needed
+    pub rows_per_batch: Option<usize>,
+
+    /// Timestamp of the first entry of each container, in microseconds since
+    /// the Unix epoch
+    pub start_time: i64,
+
+    /// Microseconds between consecutive entries of a container
+    pub interval_us: i64,
+
+    /// Advance a single clock by `interval_us` for every entry of every
+    /// batch, rather than restarting from `start_time` for every container,
+    /// so the output is sorted by `time` end to end
+    pub monotonic_time: bool,
+
+    /// If set, move the evenly spaced entries so their density follows a
+    /// day/night pattern, the offsets of `interval_us` from `start_time`
+    /// should span days for the pattern to show
+    pub diurnal: Option<Diurnal>,
+
+    /// Unit of the `time` column, the generated instants are the same for
+    /// either unit
+    pub time_unit: TimeUnitConfig,
+
+    /// Timezone stored in the `time` column's type, e.g. `UTC` or
+    /// `America/New_York`
+    ///
+    /// Only changes how readers interpret the values, which remain relative
+    /// to the Unix epoch in UTC
+    pub timezone: Option<String>,
+
+    /// If set, the host of each batch cycles through this many distinct
+    /// hosts rather than every batch having a new host, must not be 0
+    pub num_hosts: Option<usize>,
+
+    /// Template of the `host` column, where `{n}` is replaced with the index
+    /// of the host and `{hex}` with 16 hex digits derived from it, defaults
+    /// to [`DEFAULT_HOST_FORMAT`]
+    pub host_format: String,
+
+    /// Services that may run on each host, must not be empty
+    pub services: Vec<String>,
+
+    /// Probability a given service runs on a host, in `[0.0, 1.0]`
+    pub service_probability: f64,
+
+    /// Number of pods of each service on a host, must not be empty or
+    /// include 0
+    pub pods_per_service: RangeInclusive<usize>,
+
+    /// Number of containers of each pod, must not be empty or include 0
+    pub containers_per_pod: RangeInclusive<usize>,
+
+    /// Length of the random pod names, must not be empty
+    pub pod_name_len: RangeInclusive<usize>,
+
+    /// Generate the pods of each service on a host in name order, so the
+    /// `pod` column is sorted within each service, or in random order if
+    /// unset
+    pub sort_pods: bool,
+
+    /// Template of the `container` column, where `{service}` is replaced
+    /// with the service and `{idx}` with the index of the container in its
+    /// pod, defaults to [`DEFAULT_CONTAINER_TEMPLATE`]
+    pub container_template: String,
+
+    /// Template of the `image` column, with the placeholders of
+    /// [`Self::container_template`] and `{digest}`, replaced with 64 hex
+    /// digits, defaults to [`DEFAULT_IMAGE_TEMPLATE`]
+    pub image_template: String,
+
+    /// Give the `image` of every container a random digest drawn from the
+    /// seed, rather than every image sharing the same fixed digest
+    pub unique_digests: bool,
+
+    /// Distribution of the `response_status` column
+    pub status_weights: Weighted<u16>,
+
+    /// Make `response_status` nullable, as some log sources drop it, with
+    /// a default null rate of `0.01`
+    pub nullable_status: bool,
+
+    /// Distribution of the `request_method` column
+    pub method_weights: Weighted<String>,
+
+    /// Build the repetitive string columns as `Dictionary(Int32, Utf8)`
+    pub dictionary: bool,
+
+    /// Address family of `client_addr`
+    pub ip_version: IpVersion,
+
+    /// Fraction of IPv6 addresses when `ip_version` is [`IpVersion::Mixed`]
+    pub ipv6_ratio: f64,
+
+    /// Draw `client_addr` from public address blocks rather than uniformly
+    pub realistic_ips: bool,
+
+    /// Add a `geo_country` column with the country of `client_addr`'s block,
+    /// requires `realistic_ips`
+    pub geo_country: bool,
+
+    /// Distribution of the `request_duration_ns` column, which holds
+    /// durations in nanoseconds
+    pub durations: Durations,
+
+    /// Add a `request_duration_ms` column of `request_duration_ns` in whole
+    /// milliseconds, rounded down
+    pub duration_ms: bool,
+
+    /// Add an `upstream_duration_ns` column of the time spent in the
+    /// backend behind a proxy, a random [`UPSTREAM_SHARE`] of
+    /// `request_duration_ns`, the rest being the proxy's overhead
+    pub upstream_time: bool,
+
+    /// Type of the `request_bytes` and `response_bytes` columns
+    pub byte_count_type: ByteCountType,
+
+    /// Distribution of the `request_bytes` column
+    pub request_sizes: Sizes,
+
+    /// Distribution of the `response_bytes` column, unless
+    /// `correlate_status` is set
+    pub response_sizes: Sizes,
+
+    /// If set, inject windows of 503s or slow requests into
+    /// `response_status` and `request_duration_ns`
+    pub spikes: Option<Spikes>,
+
+    /// Derive `request_duration_ns` and `response_bytes` from
+    /// `response_status`, rather than drawing them independently
+    ///
+    /// Responses without a body, i.e. 204 and 304, have 0 bytes, other 2xx
+    /// have up to 1MiB and take longer the larger they are, 3xx and 4xx are
+    /// small and fast, and 5xx are small, the fastest and more often have no
+    /// recorded size
+    pub correlate_status: bool,
+
+    /// How `request_user_agent` is generated
+    pub user_agents: UserAgents,
+
+    /// Path templates of the `request_path` column
+    pub routes: Routes,
+
+    /// Add a nullable `request_query` column
+    pub request_query: bool,
+
+    /// If set, add a `request_protocol` column such as `HTTP/2` with this
+    /// distribution, see [`DEFAULT_PROTOCOL_WEIGHTS`]
+    pub protocols: Option<Weighted<String>>,
+
+    /// If set, add a `session_id` column where consecutive entries share a
+    /// session, with the client address and user agent, for this many
+    /// entries on average, must not be 0
+    pub session_length: Option<usize>,
+
+    /// If set, add a nullable `headers` column of type `List<Utf8>` with
+    /// this many random request headers per entry, such as
+    /// `Accept: text/html`
+    pub headers: Option<RangeInclusive<usize>>,
+
+    /// Add a `query_params` column of type `Map<Utf8, Utf8>` with up to 3
+    /// distinct parameters per entry, such as `page` or `sort`, which may
+    /// be empty
+    pub query_params: bool,
+
+    /// Add a `service_tier` column with the [`service_tier`] of `service`,
+    /// null where `service` is
+    pub service_tier: bool,
+
+    /// Add a `level` column of log levels following `response_status`,
+    /// `ERROR` for 5xx and `WARN` for 4xx statuses, and otherwise `INFO`, or
+    /// `DEBUG` with probability [`DEBUG_LEVEL_PROBABILITY`]
+    pub level: bool,
+
+    /// Add `trace_id` and `span_id` columns of 32 and 16 hex digits, where
+    /// entries of the same pod within a second of a trace's first entry join
+    /// it with probability [`TRACE_JOIN_PROBABILITY`]
+    pub tracing: bool,
+
+    /// If set, add a `schema_version` column with this value in every entry,
+    /// to combine files of several versions in schema evolution tests
+    pub schema_version: Option<i32>,
+
+    /// Add a `_batch_id` column with the index of the host each entry was
+    /// generated for, before wrapping at [`Self::num_hosts`], to trace rows
+    /// back to their batch through partitioning and shuffling
+    pub batch_id: bool,
+
+    /// Fraction of `request_referer` values that are null, i.e. direct hits
+    pub referer_null_rate: f64,
+
+    /// Fraction of non-null `request_referer` values pointing to the same
+    /// service rather than an external site
+    pub referer_internal_ratio: f64,
+
+    /// Probability each listed nullable column is null, in `[0.0, 1.0]`
+    ///
+    /// Columns not listed keep their default rate, i.e. `0.1` for
+    /// `request_bytes` and `response_bytes`, `referer_null_rate` for
+    /// `request_referer`, `0.6` for `request_query`, `0.01` for
+    /// `response_status` with `nullable_status`, `0.05` for `headers` and
+    /// `0.0` otherwise
+    pub null_rates: HashMap<String, f64>,
+
+    /// Replace every random choice with a fixed value, for golden files
+    ///
+    /// Every service runs on every host, each with the two pods
+    /// `<service>-pod-0` and `<service>-pod-1` of a single container with
+    /// 1024 entries, and every entry has:
+    ///
+    /// - `service` and other host columns as usual, and `time` from the
+    ///   entry's position as usual
+    /// - `client_addr` `10.0.0.1`, or `2001:db8::1` with [`IpVersion::V6`]
+    /// - `request_duration_ns` 1000000, i.e. 1ms, and `upstream_duration_ns`
+    ///   900000
+    /// - `request_user_agent` [`DETERMINISTIC_USER_AGENT`]
+    /// - `request_method` `GET`, `request_path` `/`, and null
+    ///   `request_referer` and `request_query`
+    /// - `request_bytes` 512, `response_bytes` 1024 and `response_status` 200
+    /// - `geo_country` `ZZ`, `session_id` and `trace_id` 32 zeros, `span_id`
+    ///   16 zeros, and empty `headers` and `query_params`
+    ///
+    /// [`Self::null_rates`] is ignored, so no other column is null
+    pub deterministic: bool,
+
+    /// If set, only these columns are in the schema and batches, in schema
+    /// order, must be names of columns of the full schema
+    ///
+    /// Omitted columns are still drawn, so the remaining columns have the
+    /// same values as with every column, except that [`Self::spikes`] are
+    /// only injected if `time` is included
+    pub columns: Option<Vec<String>>,
+
+    /// Group the `request_*` and `response_*` columns into `request` and
+    /// `response` struct columns, e.g. `request_method` becomes
+    /// `request.method`, with the same values
+    ///
+    /// Other options, e.g. [`Self::columns`] and [`Self::null_rates`], still
+    /// refer to the columns by their flat names
+    pub nested: bool,
+
+    /// Randomly permute the rows of each batch once they are generated, so
+    /// the statistics of row groups overlap rather than being sorted by pod
+    /// and time
+    ///
+    /// The permutation is drawn from an RNG of its own, so the rows have the
+    /// same values as without shuffling, only in a different order
+    pub shuffle: bool,
+}
+
+/// The address family of generated client addresses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpVersion {
+    V4,
+    V6,
+    /// A mix of both, see [`GeneratorConfig::ipv6_ratio`]
+    Mixed,
+}
+
+impl FromStr for IpVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "v4" => Ok(Self::V4),
+            "v6" => Ok(Self::V6),
+            "mixed" => Ok(Self::Mixed),
+            _ => Err(format!(
+                "unknown IP version \"{}\", expected v4, v6 or mixed",
+                s
+            )),
+        }
+    }
+}
+
+/// The supported units of the `time` column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnitConfig {
+    Micros,
+    Nanos,
+}
+
+impl TimeUnitConfig {
+    fn time_unit(self) -> TimeUnit {
+        match self {
+            Self::Micros => TimeUnit::Microsecond,
+            Self::Nanos => TimeUnit::Nanosecond,
+        }
+    }
+}
+
+impl FromStr for TimeUnitConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "micros" => Ok(Self::Micros),
+            "nanos" => Ok(Self::Nanos),
+            _ => Err(format!(
+                "unknown time unit \"{}\", expected micros or nanos",
+                s
+            )),
+        }
+    }
+}
+
+/// The supported types of the `request_bytes` and `response_bytes` columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteCountType {
+    /// `Int32`, where sizes of 2GiB or more are `i32::MAX`
+    Int32,
+    Int64,
+    /// `Decimal128(38, 0)`, which parquet stores as fixed length byte arrays
+    Decimal,
+}
+
+impl ByteCountType {
+    fn data_type(self) -> DataType {
+        match self {
+            Self::Int32 => DataType::Int32,
+            Self::Int64 => DataType::Int64,
+            Self::Decimal => DataType::Decimal128(38, 0),
+        }
+    }
+}
+
+impl FromStr for ByteCountType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "int32" => Ok(Self::Int32),
+            "int64" => Ok(Self::Int64),
+            "decimal" => Ok(Self::Decimal),
+            _ => Err(format!(
+                "unknown byte count type \"{}\", expected int32, int64 or decimal",
+                s
+            )),
+        }
+    }
+}
+
+/// The EC2 style host names of [`GeneratorConfig::host_format`]
+pub const DEFAULT_HOST_FORMAT: &str = "i-{hex}.ec2.internal";
+
+/// The container names of [`GeneratorConfig::container_template`], e.g.
+/// `frontend_container_0`
+pub const DEFAULT_CONTAINER_TEMPLATE: &str = "{service}_container_{idx}";
+
+/// The images of [`GeneratorConfig::image_template`], named after their
+/// container
+pub const DEFAULT_IMAGE_TEMPLATE: &str = "{service}_container_{idx}@sha256:{digest}";
+
+/// Approximate response status ratios of typical web traffic
+pub const DEFAULT_STATUS_WEIGHTS: &str =
+    "200:0.85,204:0.03,301:0.02,304:0.04,400:0.02,403:0.01,404:0.02,500:0.005,503:0.005";
+
+/// The `request_user_agent` of every entry with
+/// [`GeneratorConfig::deterministic`]
+pub const DETERMINISTIC_USER_AGENT: &str =
+    "Mozilla/5.0 (X11; Linux x86_64; rv:120.0) Gecko/20100101 Firefox/120.0";
+
+/// The digest of every `image` unless [`GeneratorConfig::unique_digests`] is
+/// set
+const FIXED_DIGEST: &str = "30375999bf03beec2187843017b10c9e88d8b1a91615df4eb6350fb39472edd9";
+
+/// Mean size of request bodies in bytes
+pub const DEFAULT_REQUEST_BYTES_MEAN: f64 = 512.;
+
+/// Mean size of response bodies in bytes
+pub const DEFAULT_RESPONSE_BYTES_MEAN: f64 = 16384.;
+
+/// The six request methods with equal weights
+pub const DEFAULT_METHOD_WEIGHTS: &str = "GET:1,PUT:1,POST:1,HEAD:1,PATCH:1,DELETE:1";
+
+/// Mostly HTTP/1.1 and HTTP/2, with some HTTP/3
+pub const DEFAULT_PROTOCOL_WEIGHTS: &str = "HTTP/1.1:5,HTTP/2:4,HTTP/3:1";
+
+/// Probability an entry joins the current trace of its pod with
+/// [`GeneratorConfig::tracing`], rather than starting a new one
+pub const TRACE_JOIN_PROBABILITY: f64 = 0.2;
+
+/// Probability the `level` of an entry with neither a 4xx nor a 5xx status
+/// is `DEBUG` rather than `INFO` with [`GeneratorConfig::level`]
+pub const DEBUG_LEVEL_PROBABILITY: f64 = 0.1;
+
+/// Range of the share of `request_duration_ns` in `upstream_duration_ns` with
+/// [`GeneratorConfig::upstream_time`]
+pub const UPSTREAM_SHARE: std::ops::Range<f64> = 0.8..1.0;
+
+/// How long after its first entry a trace can be joined, in microseconds
+pub const TRACE_WINDOW_US: i64 = 1_000_000;
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            rows_per_batch: None,
+            start_time: 0,
+            interval_us: 1024,
+            monotonic_time: false,
+            diurnal: None,
+            time_unit: TimeUnitConfig::Micros,
+            timezone: None,
+            num_hosts: None,
+            host_format: DEFAULT_HOST_FORMAT.to_string(),
+            services: ["frontend", "backend", "database", "cache"]
+                .map(String::from)
+                .to_vec(),
+            service_probability: 0.5,
+            pods_per_service: 1..=14,
+            containers_per_pod: 1..=2,
+            pod_name_len: 30..=39,
+            sort_pods: true,
+            container_template: DEFAULT_CONTAINER_TEMPLATE.to_string(),
+            image_template: DEFAULT_IMAGE_TEMPLATE.to_string(),
+            unique_digests: false,
+            status_weights: DEFAULT_STATUS_WEIGHTS.parse().unwrap(),
+            nullable_status: false,
+            method_weights: DEFAULT_METHOD_WEIGHTS.parse().unwrap(),
+            dictionary: false,
+            ip_version: IpVersion::V4,
+            ipv6_ratio: 0.5,
+            realistic_ips: false,
+            geo_country: false,
+            durations: Durations::default(),
+            duration_ms: false,
+            upstream_time: false,
+            byte_count_type: ByteCountType::Int32,
+            request_sizes: Sizes::requests(DEFAULT_REQUEST_BYTES_MEAN).unwrap(),
+            response_sizes: Sizes::responses(DEFAULT_RESPONSE_BYTES_MEAN).unwrap(),
+            spikes: None,
+            correlate_status: false,
+            user_agents: UserAgents::Builtin(DEFAULT_USER_AGENT_WEIGHTS.parse().unwrap()),
+            routes: Routes::default(),
+            request_query: false,
+            protocols: None,
+            session_length: None,
+            headers: None,
+            query_params: false,
+            service_tier: false,
+            level: false,
+            tracing: false,
+            schema_version: None,
+            batch_id: false,
+            referer_null_rate: 0.3,
+            referer_internal_ratio: 0.6,
+            null_rates: HashMap::new(),
+            deterministic: false,
+            columns: None,
+            nested: false,
+            shuffle: false,
+        }
+    }
+}
+
+impl GeneratorConfig {
+    /// Returns true if `column` is in the schema, unless it is omitted by
+    /// [`Self::columns`]
+    fn includes(&self, column: &str) -> bool {
+        match &self.columns {
+            Some(columns) => columns.iter().any(|c| c == column),
+            None => true,
+        }
+    }
+}
+
+/// The session of the entries appended to a [`BatchBuilder`], see
+/// [`GeneratorConfig::session_length`]
+#[derive(Debug, Clone)]
+struct Session {
+    id: String,
+    client_addr: String,
+    country: Option<&'static str>,
+    user_agent: String,
+}
+
+/// The trace of the entries appended to a [`BatchBuilder`], see
+/// [`GeneratorConfig::tracing`]
+#[derive(Debug)]
+struct Trace {
+    id: String,
+    pod: String,
+    start: i64,
+}
+
+/// The response of an entry with [`GeneratorConfig::correlate_status`]
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    duration_ns: i64,
+    bytes: Option<i64>,
+}
+
+/// The last container appended to a [`BatchBuilder`], used to pad a batch
+/// up to [`GeneratorConfig::rows_per_batch`]
+#[derive(Debug)]
+struct LastContainer {
+    host: String,
+    pod: String,
+    service: String,
+    container: String,
+    image: String,
+    num_entries: usize,
+}
+
+/// A string column built as either `Utf8` or `Dictionary(Int32, Utf8)`
+enum StringColumn {
+    Plain(StringBuilder),
+    Dictionary(StringDictionaryBuilder<Int32Type>),
+}
+
+impl Default for StringColumn {
+    fn default() -> Self {
+        Self::Plain(StringBuilder::new())
+    }
+}
+
+impl StringColumn {
+    fn new(dictionary: bool) -> Self {
+        if dictionary {
+            Self::Dictionary(StringDictionaryBuilder::new())
+        } else {
+            Self::Plain(StringBuilder::new())
+        }
+    }
+
+    fn data_type(dictionary: bool) -> DataType {
+        if dictionary {
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8))
+        } else {
+            DataType::Utf8
+        }
+    }
+
+    fn append_value(&mut self, value: &str) {
+        match self {
+            Self::Plain(b) => b.append_value(value),
+            Self::Dictionary(b) => {
+                b.append_value(value);
+            }
+        }
+    }
+
+    fn append_option(&mut self, value: Option<&str>) {
+        match (self, value) {
+            (Self::Plain(b), value) => b.append_option(value),
+            (Self::Dictionary(b), Some(value)) => {
+                b.append_value(value);
+            }
+            (Self::Dictionary(b), None) => b.append_null(),
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::Plain(b) => Arc::new(b.finish()),
+            Self::Dictionary(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+/// A builder of the `request_bytes` and `response_bytes` columns in any
+/// [`ByteCountType`]
+#[derive(Debug)]
+enum ByteColumn {
+    Int32(Int32Builder),
+    Int64(Int64Builder),
+    Decimal(Decimal128Builder),
+}
+
+impl Default for ByteColumn {
+    fn default() -> Self {
+        Self::Int32(Int32Builder::new())
+    }
+}
+
+impl ByteColumn {
+    fn new(data_type: ByteCountType) -> Self {
+        match data_type {
+            ByteCountType::Int32 => Self::Int32(Int32Builder::new()),
+            ByteCountType::Int64 => Self::Int64(Int64Builder::new()),
+            ByteCountType::Decimal => Self::Decimal(
+                Decimal128Builder::new().with_data_type(ByteCountType::Decimal.data_type()),
+            ),
+        }
+    }
+
+    fn append_option(&mut self, bytes: Option<i64>) {
+        match self {
+            Self::Int32(b) => b.append_option(bytes.map(|bytes| bytes.min(i32::MAX as i64) as i32)),
+            Self::Int64(b) => b.append_option(bytes),
+            Self::Decimal(b) => b.append_option(bytes.map(i128::from)),
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::Int32(b) => Arc::new(b.finish()),
+            Self::Int64(b) => Arc::new(b.finish()),
+            Self::Decimal(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+/// A builder of the `time` column in either [`TimeUnitConfig`]
+#[derive(Debug)]
+enum TimeColumn {
+    Micros(TimestampMicrosecondBuilder),
+    Nanos(TimestampNanosecondBuilder),
+}
+
+impl Default for TimeColumn {
+    fn default() -> Self {
+        Self::Micros(TimestampMicrosecondBuilder::new())
+    }
+}
+
+impl TimeColumn {
+    fn new(unit: TimeUnitConfig, timezone: Option<&str>) -> Self {
+        match unit {
+            TimeUnitConfig::Micros => {
+                Self::Micros(TimestampMicrosecondBuilder::new().with_timezone_opt(timezone))
+            }
+            TimeUnitConfig::Nanos => {
+                Self::Nanos(TimestampNanosecondBuilder::new().with_timezone_opt(timezone))
+            }
+        }
+    }
+
+    /// Appends `micros` since the epoch, scaled to the column's unit
+    fn append_value(&mut self, micros: i64) {
+        match self {
+            Self::Micros(b) => b.append_value(micros),
+            Self::Nanos(b) => b.append_value(micros * 1000),
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::Micros(b) => Arc::new(b.finish()),
+            Self::Nanos(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+/// Builds a RecordBatch of access log entries
+#[derive(Default)]
+pub struct BatchBuilder {
+    config: Arc<GeneratorConfig>,
+    num_rows: usize,
+    /// The host index last passed to [`Self::append_host`]
+    host_idx: usize,
+    last: Option<LastContainer>,
+    session: Option<Session>,
+    trace: Option<Trace>,
+
+    service: StringColumn,
+    host: StringColumn,
+    pod: StringColumn,
+    container: StringColumn,
+    image: StringColumn,
+    time: TimeColumn,
+    client_addr: StringBuilder,
+    request_duration_ns: Int64Builder,
+    upstream_duration_ns: Option<Int64Builder>,
+    request_user_agent: StringBuilder,
+    request_method: StringColumn,
+    request_host: StringBuilder,
+    request_path: StringBuilder,
+    request_referer: StringBuilder,
+    request_query: Option<StringBuilder>,
+    request_protocol: Option<StringColumn>,
+    request_bytes: ByteColumn,
+    response_bytes: ByteColumn,
+    response_status: UInt16Builder,
+    geo_country: Option<StringBuilder>,
+    session_id: Option<StringBuilder>,
+    headers: Option<ListBuilder<StringBuilder>>,
+    query_params: Option<MapBuilder<StringBuilder, StringBuilder>>,
+    service_tier: Option<StringBuilder>,
+    level: Option<StringColumn>,
+    trace_id: Option<StringBuilder>,
+    span_id: Option<StringBuilder>,
+}
+
+impl BatchBuilder {
+    pub fn new(config: Arc<GeneratorConfig>) -> Self {
+        let dictionary = config.dictionary;
+        Self {
+            service: StringColumn::new(dictionary),
+            host: StringColumn::new(dictionary),
+            pod: StringColumn::new(dictionary),
+            container: StringColumn::new(dictionary),
+            image: StringColumn::new(dictionary),
+            time: TimeColumn::new(config.time_unit, config.timezone.as_deref()),
+            request_method: StringColumn::new(dictionary),
+            upstream_duration_ns: config.upstream_time.then(Int64Builder::new),
+            request_bytes: ByteColumn::new(config.byte_count_type),
+            response_bytes: ByteColumn::new(config.byte_count_type),
+            request_query: config.request_query.then(StringBuilder::new),
+            request_protocol: config
+                .protocols
+                .as_ref()
+                .map(|_| StringColumn::new(dictionary)),
+            geo_country: config.geo_country.then(StringBuilder::new),
+            session_id: config.session_length.map(|_| StringBuilder::new()),
+            headers: config
+                .headers
+                .as_ref()
+                .map(|_| ListBuilder::new(StringBuilder::new())),
+            query_params: config
+                .query_params
+                .then(|| MapBuilder::new(None, StringBuilder::new(), StringBuilder::new())),
+            service_tier: config.service_tier.then(StringBuilder::new),
+            level: config.level.then(|| StringColumn::new(dictionary)),
+            trace_id: config.tracing.then(StringBuilder::new),
+            span_id: config.tracing.then(StringBuilder::new),
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the number of rows appended so far
+    pub fn len(&self) -> usize {
+        self.num_rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_rows == 0
+    }
+
+    /// Returns true if [`GeneratorConfig::rows_per_batch`] rows have been appended
+    pub fn is_full(&self) -> bool {
+        self.config
+            .rows_per_batch
+            .is_some_and(|limit| self.num_rows >= limit)
+    }
+
+    /// Returns the schema of the batches built with `config`
+    pub fn schema(config: &GeneratorConfig) -> SchemaRef {
+        let schema = Self::flat_schema(config);
+        if config.nested {
+            nest(RecordBatch::new_empty(schema)).schema()
+        } else {
+            schema
+        }
+    }
+
+    /// Returns the schema of the batches built with `config` ignoring
+    /// [`GeneratorConfig::nested`], whose columns are the leaves of
+    /// [`Self::schema`] in the same order
+    pub fn flat_schema(config: &GeneratorConfig) -> SchemaRef {
+        let fields = Self::fields(config)
+            .into_iter()
+            .filter(|field| config.includes(field.name()));
+        Arc::new(Schema::new(fields.collect::<Vec<_>>()))
+    }
+
+    /// Returns every field of the schema, ignoring
+    /// [`GeneratorConfig::columns`]
+    fn fields(config: &GeneratorConfig) -> Vec<Field> {
+        let utf8_dict = || StringColumn::data_type(config.dictionary);
+
+        let mut fields = vec![
+            Field::new("service", utf8_dict(), true),
+            Field::new("host", utf8_dict(), false),
+            Field::new("pod", utf8_dict(), false),
+            Field::new("container", utf8_dict(), false),
+            Field::new("image", utf8_dict(), false),
+            Field::new(
+                "time",
+                DataType::Timestamp(
+                    config.time_unit.time_unit(),
+                    config.timezone.as_deref().map(Into::into),
+                ),
+                false,
+            ),
+            Field::new("client_addr", DataType::Utf8, true),
+            Field::new("request_duration_ns", DataType::Int64, false),
+            Field::new("request_user_agent", DataType::Utf8, true),
+            Field::new("request_method", utf8_dict(), true),
+            Field::new("request_host", DataType::Utf8, true),
+            Field::new("request_path", DataType::Utf8, false),
+            Field::new("request_referer", DataType::Utf8, true),
+        ];
+        if config.request_query {
+            fields.push(Field::new("request_query", DataType::Utf8, true));
+        }
+        if config.protocols.is_some() {
+            fields.push(Field::new("request_protocol", utf8_dict(), true));
+        }
+        if config.duration_ms {
+            fields.push(Field::new("request_duration_ms", DataType::Int64, false));
+        }
+        fields.extend([
+            Field::new("request_bytes", config.byte_count_type.data_type(), true),
+            Field::new("response_bytes", config.byte_count_type.data_type(), true),
+            Field::new("response_status", DataType::UInt16, config.nullable_status),
+        ]);
+        // After the request and response columns, which --nested groups
+        if config.upstream_time {
+            fields.push(Field::new("upstream_duration_ns", DataType::Int64, false));
+        }
+        if config.geo_country {
+            fields.push(Field::new("geo_country", DataType::Utf8, false));
+        }
+        if config.session_length.is_some() {
+            fields.push(Field::new("session_id", DataType::Utf8, false));
+        }
+        if config.headers.is_some() {
+            fields.push(Field::new_list(
+                "headers",
+                Field::new("item", DataType::Utf8, true),
+                true,
+            ));
+        }
+        if config.query_params {
+            // The field names and nullability of MapBuilder
+            fields.push(Field::new_map(
+                "query_params",
+                "entries",
+                Field::new("keys", DataType::Utf8, false),
+                Field::new("values", DataType::Utf8, true),
+                false,
+                true,
+            ));
+        }
+        if config.service_tier {
+            fields.push(Field::new("service_tier", DataType::Utf8, true));
+        }
+        if config.level {
+            fields.push(Field::new("level", utf8_dict(), false));
+        }
+        if config.tracing {
+            fields.push(Field::new("trace_id", DataType::Utf8, false));
+            fields.push(Field::new("span_id", DataType::Utf8, false));
+        }
+        if config.schema_version.is_some() {
+            fields.push(Field::new("schema_version", DataType::Int32, false));
+        }
+        if config.batch_id {
+            fields.push(Field::new("_batch_id", DataType::Int32, false));
+        }
+        fields
+    }
+
+    /// Appends the entries of every service running on the host `host_idx`
+    ///
+    /// If [`GeneratorConfig::num_hosts`] is set the host name cycles through
+    /// that many distinct hosts
+    pub fn append_host(&mut self, rng: &mut StdRng, host_idx: usize) {
+        let config = Arc::clone(&self.config);
+        self.host_idx = host_idx;
+        let host_idx = match config.num_hosts {
+            Some(num_hosts) => host_idx % num_hosts,
+            None => host_idx,
+        };
+        let host = config
+            .host_format
+            .replace("{n}", &host_idx.to_string())
+            .replace(
+                "{hex}",
+                &format!("{:016x}", host_idx * 0x7d87f8ed5c5 + 0x1ec3ca3151468928),
+            );
+
+        for service in &config.services {
+            if self.is_full() {
+                break;
+            }
+            if !config.deterministic && rng.gen_bool(1.0 - config.service_probability) {
+                continue;
+            }
+            self.append(rng, &host, service);
+        }
+
+        if config.rows_per_batch.is_some() {
+            if self.is_empty() {
+                // Every service was skipped, fall back to the first
+                self.append(rng, &host, &config.services[0]);
+            }
+            self.pad(rng);
+        }
+    }
+
+    pub fn append(&mut self, rng: &mut StdRng, host: &str, service: &str) {
+        let deterministic = self.config.deterministic;
+        let pods = if deterministic {
+            (0..2).map(|i| format!("{}-pod-{}", service, i)).collect()
+        } else {
+            let num_pods = rng.gen_range(self.config.pods_per_service.clone());
+            generate_strings(
+                rng,
+                num_pods,
+                self.config.pod_name_len.clone(),
+                self.config.sort_pods,
+            )
+        };
+        for pod in pods {
+            let num_containers = if deterministic {
+                1
+            } else {
+                rng.gen_range(self.config.containers_per_pod.clone())
+            };
+            for container_idx in 0..num_containers {
+                let digest = if self.config.unique_digests && !deterministic {
+                    format!("{:032x}{:032x}", rng.gen::<u128>(), rng.gen::<u128>())
+                } else {
+                    FIXED_DIGEST.to_string()
+                };
+                let fill = |template: &str| {
+                    template
+                        .replace("{service}", service)
+                        .replace("{idx}", &container_idx.to_string())
+                        .replace("{digest}", &digest)
+                };
+                let container = fill(&self.config.container_template);
+                let image = fill(&self.config.image_template);
+
+                let num_entries = if deterministic {
+                    1024
+                } else {
+                    rng.gen_range(1024..8192)
+                };
+                for i in 0..num_entries {
+                    if self.is_full() {
+                        return;
+                    }
+                    let time = i as i64 * self.config.interval_us;
+                    self.append_row(rng, host, &pod, service, &container, &image, time);
+                }
+
+                self.last = Some(LastContainer {
+                    host: host.to_string(),
+                    pod: pod.clone(),
+                    service: service.to_string(),
+                    container,
+                    image,
+                    num_entries,
+                });
+            }
+        }
+    }
+
+    /// Appends further rows to the last container until the batch contains
+    /// [`GeneratorConfig::rows_per_batch`] rows
+    pub fn pad(&mut self, rng: &mut StdRng) {
+        let limit = match self.config.rows_per_batch {
+            Some(limit) if self.num_rows < limit => limit,
+            _ => return,
+        };
+        let last = match self.last.take() {
+            Some(last) => last,
+            None => return,
+        };
+
+        let num_entries = last.num_entries + limit - self.num_rows;
+        for i in last.num_entries..num_entries {
+            let time = i as i64 * self.config.interval_us;
+            self.append_row(
+                rng,
+                &last.host,
+                &last.pod,
+                &last.service,
+                &last.container,
+                &last.image,
+                time,
+            );
+        }
+        self.last = Some(LastContainer {
+            num_entries,
+            ..last
+        });
+    }
+
+    /// Appends a single random entry, `time` is the offset in microseconds
+    /// from [`GeneratorConfig::start_time`]
+    #[allow(clippy::too_many_arguments)]
+    pub fn append_row(
+        &mut self,
+        rng: &mut StdRng,
+        host: &str,
+        pod: &str,
+        service: &str,
+        container: &str,
+        image: &str,
+        time: i64,
+    ) {
+        // Generator shifts each batch to start where the previous one ended
+        let time = if self.config.monotonic_time {
+            self.num_rows as i64 * self.config.interval_us
+        } else {
+            time
+        };
+        if self.config.deterministic {
+            self.append_fixed_row(host, pod, service, container, image, time);
+            return;
+        }
+
+        let null = self.is_null(rng, "service");
+        self.service.append_option((!null).then_some(service));
+        if let Some(tiers) = &mut self.service_tier {
+            tiers.append_option((!null).then(|| service_tier(service)));
+        }
+        self.host.append_value(host);
+        self.pod.append_value(pod);
+        self.container.append_value(container);
+        self.image.append_value(image);
+        self.time.append_value(self.config.start_time + time);
+
+        let session = self
+            .config
+            .session_length
+            .map(|session_length| self.next_session(rng, session_length));
+        let (client_addr, country) = match &session {
+            Some(session) => (session.client_addr.clone(), session.country),
+            None => self.random_client_addr(rng),
+        };
+        // geo_country is not nullable, so is appended even if client_addr is
+        // null
+        if let Some(geo_country) = &mut self.geo_country {
+            geo_country.append_value(country.expect("geo_country requires realistic_ips"));
+        }
+        let null = self.is_null(rng, "client_addr");
+        self.client_addr
+            .append_option((!null).then_some(client_addr));
+        let response = self
+            .config
+            .correlate_status
+            .then(|| self.correlated_response(rng));
+        let duration_ns = match &response {
+            Some(response) => response.duration_ns,
+            None => self.config.durations.sample(rng),
+        };
+        self.request_duration_ns.append_value(duration_ns);
+        if let Some(upstream) = &mut self.upstream_duration_ns {
+            let share = rng.gen_range(UPSTREAM_SHARE);
+            upstream.append_value((duration_ns as f64 * share) as i64);
+        }
+        let user_agent = match session {
+            Some(session) => session.user_agent,
+            None => self.config.user_agents.sample(rng),
+        };
+        let null = self.is_null(rng, "request_user_agent");
+        self.request_user_agent
+            .append_option((!null).then_some(user_agent));
+        let method = self.config.method_weights.sample(rng).as_str();
+        let null = self.is_null(rng, "request_method");
+        self.request_method.append_option((!null).then_some(method));
+        let null = self.is_null(rng, "request_host");
+        self.request_host
+            .append_option((!null).then(|| format!("https://{}.mydomain.com", service)));
+        self.request_path
+            .append_value(self.config.routes.sample(rng, service));
+        self.request_referer.append_option(route::random_referer(
+            rng,
+            &self.config.routes,
+            service,
+            self.null_rate("request_referer", self.config.referer_null_rate),
+            self.config.referer_internal_ratio,
+        ));
+        let query_null_rate = self.null_rate("request_query", 0.6);
+        if let Some(request_query) = &mut self.request_query {
+            request_query.append_option(route::random_query(rng, query_null_rate));
+        }
+        let null = self.is_null(rng, "request_protocol");
+        if let (Some(protocol), Some(protocols)) =
+            (&mut self.request_protocol, &self.config.protocols)
+        {
+            protocol.append_option((!null).then(|| protocols.sample(rng).as_str()));
+        }
+        let headers_null_rate = self.null_rate("headers", 0.05);
+        if let (Some(headers), Some(count)) = (&mut self.headers, &self.config.headers) {
+            match route::random_headers(rng, count.clone(), headers_null_rate) {
+                Some(values) => {
+                    for value in values {
+                        headers.values().append_value(value);
+                    }
+                    headers.append(true);
+                }
+                None => headers.append_null(),
+            }
+        }
+        let null = self.is_null(rng, "query_params");
+        if let Some(query_params) = &mut self.query_params {
+            if !null {
+                for (key, value) in route::random_query_params(rng) {
+                    query_params.keys().append_value(key);
+                    query_params.values().append_value(value);
+                }
+            }
+            query_params.append(!null).expect("as many keys as values");
+        }
+        if self.config.tracing {
+            self.append_trace(rng, pod, time);
+        }
+
+        let present = 1.0 - self.null_rate("request_bytes", 0.1);
+        self.request_bytes.append_option(
+            rng.gen_bool(present)
+                .then(|| self.config.request_sizes.sample(rng)),
+        );
+        let status = match response {
+            Some(response) => {
+                self.response_bytes.append_option(response.bytes);
+                response.status
+            }
+            None => {
+                let present = 1.0 - self.null_rate("response_bytes", 0.1);
+                self.response_bytes.append_option(
+                    rng.gen_bool(present)
+                        .then(|| self.config.response_sizes.sample(rng)),
+                );
+                *self.config.status_weights.sample(rng)
+            }
+        };
+        // Only draws with nullable_status so the default output is unchanged
+        let null =
+            self.config.nullable_status && rng.gen_bool(self.null_rate("response_status", 0.01));
+        self.response_status
+            .append_option((!null).then_some(status));
+        if let Some(level) = &mut self.level {
+            level.append_value(match status {
+                500..=599 => "ERROR",
+                400..=499 => "WARN",
+                _ if rng.gen_bool(DEBUG_LEVEL_PROBABILITY) => "DEBUG",
+                _ => "INFO",
+            });
+        }
+        self.num_rows += 1;
+    }
+
+    /// Appends an entry with the fixed values of
+    /// [`GeneratorConfig::deterministic`]
+    fn append_fixed_row(
+        &mut self,
+        host: &str,
+        pod: &str,
+        service: &str,
+        container: &str,
+        image: &str,
+        time: i64,
+    ) {
+        self.service.append_value(service);
+        if let Some(tiers) = &mut self.service_tier {
+            tiers.append_value(service_tier(service));
+        }
+        self.host.append_value(host);
+        self.pod.append_value(pod);
+        self.container.append_value(container);
+        self.image.append_value(image);
+        self.time.append_value(self.config.start_time + time);
+        self.client_addr.append_value(match self.config.ip_version {
+            IpVersion::V6 => "2001:db8::1",
+            IpVersion::V4 | IpVersion::Mixed => "10.0.0.1",
+        });
+        self.request_duration_ns.append_value(1_000_000);
+        if let Some(upstream) = &mut self.upstream_duration_ns {
+            upstream.append_value(900_000);
+        }
+        self.request_user_agent
+            .append_value(DETERMINISTIC_USER_AGENT);
+        self.request_method.append_value("GET");
+        self.request_host
+            .append_value(format!("https://{}.mydomain.com", service));
+        self.request_path.append_value("/");
+        self.request_referer.append_null();
+        if let Some(request_query) = &mut self.request_query {
+            request_query.append_null();
+        }
+        if let Some(protocol) = &mut self.request_protocol {
+            protocol.append_value("HTTP/1.1");
+        }
+        self.request_bytes.append_option(Some(512));
+        self.response_bytes.append_option(Some(1024));
+        self.response_status.append_value(200);
+        if let Some(geo_country) = &mut self.geo_country {
+            geo_country.append_value("ZZ");
+        }
+        if let Some(session_id) = &mut self.session_id {
+            session_id.append_value("00000000000000000000000000000000");
+        }
+        if let Some(headers) = &mut self.headers {
+            headers.append(true);
+        }
+        if let Some(query_params) = &mut self.query_params {
+            query_params.append(true).expect("no keys or values");
+        }
+        if let Some(level) = &mut self.level {
+            level.append_value("INFO");
+        }
+        if let (Some(trace_id), Some(span_id)) = (&mut self.trace_id, &mut self.span_id) {
+            trace_id.append_value("00000000000000000000000000000000");
+            span_id.append_value("0000000000000000");
+        }
+        self.num_rows += 1;
+    }
+
+    /// Returns a random response whose duration and size depend on its
+    /// status, see [`GeneratorConfig::correlate_status`]
+    fn correlated_response(&self, rng: &mut StdRng) -> Response {
+        let status = *self.config.status_weights.sample(rng);
+        let (bytes, duration_scale, null_rate) = match status {
+            204 | 304 => (0..1, 0.5, 0.0),
+            200..=299 => (1024..1 << 20, 1.0, 0.1),
+            300..=499 => (64..2048, 0.5, 0.1),
+            _ => (64..1024, 0.2, 0.3),
+        };
+        let bytes: i32 = rng.gen_range(bytes);
+        // A 1MiB body takes about twice as long as an empty one
+        let duration_scale = duration_scale * (1.0 + bytes as f64 / (1 << 20) as f64);
+        let duration_ns = (self.config.durations.sample(rng) as f64 * duration_scale) as i64;
+        let null = rng.gen_bool(self.null_rate("response_bytes", null_rate));
+        Response {
+            status,
+            duration_ns,
+            bytes: (!null).then_some(i64::from(bytes)),
+        }
+    }
+
+    /// Returns a random client address, and its country if
+    /// [`GeneratorConfig::realistic_ips`] is set
+    fn random_client_addr(&self, rng: &mut StdRng) -> (String, Option<&'static str>) {
+        let v6 = match self.config.ip_version {
+            IpVersion::V4 => false,
+            IpVersion::V6 => true,
+            IpVersion::Mixed => rng.gen_bool(self.config.ipv6_ratio),
+        };
+        if self.config.realistic_ips {
+            let (addr, country) = ip::random_public(rng, v6);
+            (addr.to_string(), Some(country))
+        } else if v6 {
+            // Ipv6Addr formats according to RFC 5952
+            (Ipv6Addr::from(rng.gen::<u128>()).to_string(), None)
+        } else {
+            let addr = format!(
+                "{}.{}.{}.{}",
+                rng.gen::<u8>(),
+                rng.gen::<u8>(),
+                rng.gen::<u8>(),
+                rng.gen::<u8>()
+            );
+            (addr, None)
+        }
+    }
+
+    /// Returns the session of the next entry, appending its id, starting a
+    /// new session if the previous one ended
+    ///
+    /// Each entry ends its session with probability `1 / session_length`, so
+    /// sessions are geometrically distributed with a mean of `session_length`
+    fn next_session(&mut self, rng: &mut StdRng, session_length: usize) -> Session {
+        let session = match self.session.take() {
+            Some(session) => session,
+            None => {
+                let (client_addr, country) = self.random_client_addr(rng);
+                Session {
+                    id: format!("{:032x}", rng.gen::<u128>()),
+                    client_addr,
+                    country,
+                    user_agent: self.config.user_agents.sample(rng),
+                }
+            }
+        };
+        if let Some(session_id) = &mut self.session_id {
+            session_id.append_value(&session.id);
+        }
+        if !rng.gen_bool(1.0 / session_length as f64) {
+            self.session = Some(session.clone());
+        }
+        session
+    }
+
+    /// Appends the trace and span ids of an entry of `pod` at `time`, joining
+    /// the current trace if it is of the same pod and started at most
+    /// [`TRACE_WINDOW_US`] before
+    fn append_trace(&mut self, rng: &mut StdRng, pod: &str, time: i64) {
+        let joins = self.trace.as_ref().is_some_and(|trace| {
+            trace.pod == pod
+                && time - trace.start <= TRACE_WINDOW_US
+                && rng.gen_bool(TRACE_JOIN_PROBABILITY)
+        });
+        if !joins {
+            self.trace = Some(Trace {
+                id: format!("{:032x}", rng.gen::<u128>()),
+                pod: pod.to_string(),
+                start: time,
+            });
+        }
+        let trace = self.trace.as_ref().expect("trace started above");
+        if let (Some(trace_id), Some(span_id)) = (&mut self.trace_id, &mut self.span_id) {
+            trace_id.append_value(&trace.id);
+            span_id.append_value(format!("{:016x}", rng.gen::<u64>()));
+        }
+    }
+
+    /// Returns the null rate of `column`, or `default` if not configured
+    fn null_rate(&self, column: &str, default: f64) -> f64 {
+        self.config
+            .null_rates
+            .get(column)
+            .copied()
+            .unwrap_or(default)
+    }
+
+    /// Returns if the next value of a column that is never null by default
+    /// should be null, only drawing from `rng` if a rate is configured so the
+    /// default output is unchanged
+    fn is_null(&self, rng: &mut StdRng, column: &str) -> bool {
+        self.config
+            .null_rates
+            .get(column)
+            .is_some_and(|rate| rng.gen_bool(*rate))
+    }
+
+    /// Finishes the batch, returning an error if `schema` doesn't match
+    /// [`Self::schema`] for the builder's configuration
+    pub fn finish(self, schema: SchemaRef) -> Result<RecordBatch, ArrowError> {
+        let nested = self.config.nested;
+        let batch = self.finish_flat();
+        let batch = if nested { nest(batch) } else { batch };
+        RecordBatch::try_new(schema, batch.columns().to_vec())
+    }
+
+    /// Finishes the batch with [`Self::flat_schema`]
+    fn finish_flat(mut self) -> RecordBatch {
+        let fields = Self::fields(&self.config);
+        let request_duration_ns = self.request_duration_ns.finish();
+        let mut columns: Vec<ArrayRef> = vec![
+            self.service.finish(),
+            self.host.finish(),
+            self.pod.finish(),
+            self.container.finish(),
+            self.image.finish(),
+            self.time.finish(),
+            Arc::new(self.client_addr.finish()),
+            Arc::new(request_duration_ns.clone()),
+            Arc::new(self.request_user_agent.finish()),
+            self.request_method.finish(),
+            Arc::new(self.request_host.finish()),
+            Arc::new(self.request_path.finish()),
+            Arc::new(self.request_referer.finish()),
+        ];
+        if let Some(request_query) = &mut self.request_query {
+            columns.push(Arc::new(request_query.finish()));
+        }
+        if let Some(protocol) = &mut self.request_protocol {
+            columns.push(protocol.finish());
+        }
+        if self.config.duration_ms {
+            columns.push(Arc::new(
+                request_duration_ns.unary::<_, Int64Type>(|ns| ns / 1_000_000),
+            ));
+        }
+        columns.extend([
+            self.request_bytes.finish(),
+            self.response_bytes.finish(),
+            Arc::new(self.response_status.finish()),
+        ]);
+        if let Some(upstream) = &mut self.upstream_duration_ns {
+            columns.push(Arc::new(upstream.finish()));
+        }
+        if let Some(geo_country) = &mut self.geo_country {
+            columns.push(Arc::new(geo_country.finish()));
+        }
+        if let Some(session_id) = &mut self.session_id {
+            columns.push(Arc::new(session_id.finish()));
+        }
+        if let Some(headers) = &mut self.headers {
+            columns.push(Arc::new(headers.finish()));
+        }
+        if let Some(query_params) = &mut self.query_params {
+            columns.push(Arc::new(query_params.finish()));
+        }
+        if let Some(service_tier) = &mut self.service_tier {
+            columns.push(Arc::new(service_tier.finish()));
+        }
+        if let Some(level) = &mut self.level {
+            columns.push(level.finish());
+        }
+        if let (Some(trace_id), Some(span_id)) = (&mut self.trace_id, &mut self.span_id) {
+            columns.push(Arc::new(trace_id.finish()));
+            columns.push(Arc::new(span_id.finish()));
+        }
+        if let Some(schema_version) = self.config.schema_version {
+            columns.push(Arc::new(Int32Array::from_value(
+                schema_version,
+                self.num_rows,
+            )));
+        }
+        if self.config.batch_id {
+            columns.push(Arc::new(Int32Array::from_value(
+                self.host_idx as i32,
+                self.num_rows,
+            )));
+        }
+        let columns = fields
+            .iter()
+            .zip(columns)
+            .filter(|(field, _)| self.config.includes(field.name()))
+            .map(|(_, column)| column)
+            .collect();
+        RecordBatch::try_new(Self::flat_schema(&self.config), columns)
+            .expect("columns match fields")
+    }
+}
+
+/// Returns `batch` with its `request_*` and `response_*` columns grouped into
+/// `request` and `response` struct columns, see [`GeneratorConfig::nested`]
+fn nest(batch: RecordBatch) -> RecordBatch {
+    // The name, fields and columns of the struct being built
+    type Group = (&'static str, Vec<FieldRef>, Vec<ArrayRef>);
+    fn close(group: Option<Group>, fields: &mut Vec<FieldRef>, columns: &mut Vec<ArrayRef>) {
+        if let Some((name, children, child_columns)) = group {
+            let struct_array = StructArray::new(children.into(), child_columns, None);
+            fields.push(Arc::new(Field::new(
+                name,
+                struct_array.data_type().clone(),
+                false,
+            )));
+            columns.push(Arc::new(struct_array));
+        }
+    }
+
+    let schema = batch.schema();
+    let mut fields = Vec::new();
+    let mut columns = Vec::new();
+    let mut group: Option<Group> = None;
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        let parent = ["request", "response"].into_iter().find_map(|prefix| {
+            let name = field.name().strip_prefix(prefix)?.strip_prefix('_')?;
+            Some((prefix, name))
+        });
+        // The children of each struct are consecutive in the flat schema
+        if group.as_ref().map(|group| group.0) != parent.map(|(prefix, _)| prefix) {
+            close(group.take(), &mut fields, &mut columns);
+        }
+        match parent {
+            Some((prefix, name)) => {
+                let (_, children, child_columns) =
+                    group.get_or_insert_with(|| (prefix, Vec::new(), Vec::new()));
+                children.push(Arc::new(field.as_ref().clone().with_name(name)));
+                child_columns.push(Arc::clone(column));
+            }
+            None => {
+                fields.push(Arc::clone(field));
+                columns.push(Arc::clone(column));
+            }
+        }
+    }
+    close(group, &mut fields, &mut columns);
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).expect("columns match fields")
+}
+
+/// Returns `batch` with its rows in a random order
+fn shuffle(batch: RecordBatch, rng: &mut StdRng) -> RecordBatch {
+    let mut indices: Vec<u32> = (0..batch.num_rows() as u32).collect();
+    indices.shuffle(rng);
+    take_record_batch(&batch, &UInt32Array::from(indices)).expect("indices are in bounds")
+}
+
+/// Returns `batch` with every `time` mapped by `f`, in microseconds since the
+/// epoch, or unchanged if [`GeneratorConfig::columns`] omits `time`
+fn map_time(batch: RecordBatch, f: impl Fn(i64) -> i64) -> RecordBatch {
+    let Ok(idx) = batch.schema().index_of("time") else {
+        return batch;
+    };
+    let time = batch.column(idx);
+    let shifted: ArrayRef = match time.data_type() {
+        DataType::Timestamp(TimeUnit::Microsecond, tz) => Arc::new(
+            time.as_primitive::<TimestampMicrosecondType>()
+                .unary::<_, TimestampMicrosecondType>(&f)
+                .with_timezone_opt(tz.clone()),
+        ),
+        DataType::Timestamp(TimeUnit::Nanosecond, tz) => Arc::new(
+            time.as_primitive::<TimestampNanosecondType>()
+                .unary::<_, TimestampNanosecondType>(|t| {
+                    f(t.div_euclid(1000)) * 1000 + t.rem_euclid(1000)
+                })
+                .with_timezone_opt(tz.clone()),
+        ),
+        data_type => unreachable!("unexpected time type {}", data_type),
+    };
+    let mut columns = batch.columns().to_vec();
+    columns[idx] = shifted;
+    RecordBatch::try_new(batch.schema(), columns).expect("schema unchanged")
+}
+
+/// Returns the tier of `service`, `edge` for services facing clients such as
+/// `frontend` or `gateway`, `data` for stores such as `database` or `cache`,
+/// and `app` otherwise
+///
+/// Tiers are matched on words of the name, so e.g. `user-db` is `data`
+pub fn service_tier(service: &str) -> &'static str {
+    const EDGE: &[&str] = &["frontend", "edge", "gateway", "proxy", "web", "lb", "cdn"];
+    const DATA: &[&str] = &[
+        "database", "db", "cache", "redis", "store", "storage", "queue", "kafka",
+    ];
+
+    let words: Vec<_> = service
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .map(|word| word.to_ascii_lowercase())
+        .collect();
+    let has = |names: &[&str]| words.iter().any(|word| names.contains(&word.as_str()));
+    if has(EDGE) {
+        "edge"
+    } else if has(DATA) {
+        "data"
+    } else {
+        "app"
+    }
+}
+
+pub(crate) fn random_string<R: Rng>(rng: &mut R, len_range: impl SampleRange<usize>) -> String {
+    let len = rng.gen_range(len_range);
+    (0..len)
+        .map(|_| rng.gen_range(b'a'..=b'z') as char)
+        .collect::<String>()
+}
+
+fn generate_strings(
+    rng: &mut StdRng,
+    count: usize,
+    str_len: RangeInclusive<usize>,
+    sorted: bool,
+) -> Vec<String> {
+    let mut strings: Vec<_> = (0..count)
+        .map(|_| random_string(rng, str_len.clone()))
+        .collect();
+
+    if sorted {
+        strings.sort_unstable();
+    }
+    strings
+}
+
+/// Generates sorted RecordBatch with an access log style schema for a single host
+#[derive(Debug)]
+pub struct Generator {
+    pub schema: SchemaRef,
+    config: Arc<GeneratorConfig>,
+    seed: u64,
+    host_idx: usize,
+    /// Offset in microseconds of the next batch with
+    /// [`GeneratorConfig::monotonic_time`]
+    clock: i64,
+    /// Number of batches finished, which seeds each permutation of
+    /// [`GeneratorConfig::shuffle`]
+    num_batches: u64,
+}
+
+impl Generator {
+    /// Creates a generator using the fixed default seed of 0
+    pub fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    /// Creates a generator whose output is fully determined by `seed`
+    ///
+    /// Each host draws from its own RNG seeded with [`host_seed`], so the
+    /// batch of a host is the same however many batches come before it
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            schema: BatchBuilder::schema(&GeneratorConfig::default()),
+            config: Default::default(),
+            seed,
+            host_idx: 0,
+            clock: 0,
+            num_batches: 0,
+        }
+    }
+
+    /// Sets the configuration used for subsequent batches
+    pub fn with_config(mut self, config: GeneratorConfig) -> Self {
+        self.schema = BatchBuilder::schema(&config);
+        self.config = Arc::new(config);
+        self
+    }
+
+    /// Generates the next `num_batches` batches in parallel, returning them
+    /// in host order
+    ///
+    /// As every host has its own RNG, the batches are the same as those of
+    /// [`Iterator::next`] regardless of scheduling
+    pub fn par_batches(&mut self, num_batches: usize) -> Vec<RecordBatch> {
+        let hosts = self.host_idx..self.host_idx + num_batches;
+        self.host_idx += num_batches;
+
+        let batches: Vec<_> = hosts
+            .into_par_iter()
+            .map(|host_idx| {
+                let mut rng = StdRng::seed_from_u64(host_seed(self.seed, host_idx));
+                let mut builder = BatchBuilder::new(Arc::clone(&self.config));
+                builder.append_host(&mut rng, host_idx);
+                builder.finish_flat()
+            })
+            .collect();
+        batches
+            .into_iter()
+            .map(|batch| self.finish_batch(batch))
+            .collect()
+    }
+
+    /// Returns an iterator of the next `num_batches` batches, generating them
+    /// [`rayon::current_num_threads`] at a time with [`Self::par_batches`]
+    pub fn par_chunks(mut self, num_batches: usize) -> impl Iterator<Item = RecordBatch> {
+        let chunk_size = rayon::current_num_threads();
+        let mut remaining = num_batches;
+        std::iter::from_fn(move || {
+            if remaining == 0 {
+                return None;
+            }
+            let num_batches = remaining.min(chunk_size);
+            remaining -= num_batches;
+            Some(self.par_batches(num_batches))
+        })
+        .flatten()
+    }
+
+    /// Returns an iterator of the rows of the batches [`Iterator::next`]
+    /// would return, in the same order and with the same values
+    pub fn rows(self) -> impl Iterator<Item = LogRow> {
+        self.flat_map(|batch| LogRow::from_batch(&batch))
+    }
+
+    /// Shifts the flat `batch` to start at the clock if
+    /// [`GeneratorConfig::monotonic_time`] is set, advancing the clock past
+    /// it, then applies [`GeneratorConfig::diurnal`],
+    /// [`GeneratorConfig::spikes`], [`GeneratorConfig::shuffle`] and
+    /// [`GeneratorConfig::nested`]
+    fn finish_batch(&mut self, batch: RecordBatch) -> RecordBatch {
+        self.num_batches += 1;
+        let batch = if self.config.monotonic_time {
+            let offset = self.clock;
+            self.clock += batch.num_rows() as i64 * self.config.interval_us;
+            map_time(batch, |t| t + offset)
+        } else {
+            batch
+        };
+        let batch = match self.config.diurnal {
+            Some(diurnal) => {
+                let start = self.config.start_time;
+                map_time(batch, |t| diurnal.warp(start, t - start))
+            }
+            None => batch,
+        };
+        let batch = match &self.config.spikes {
+            Some(spikes) => spikes.apply(batch),
+            None => batch,
+        };
+        let batch = if self.config.shuffle {
+            // Unmixed unlike host_seed, so the streams differ
+            let seed = self.seed ^ self.num_batches.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+            shuffle(batch, &mut StdRng::seed_from_u64(seed))
+        } else {
+            batch
+        };
+        if self.config.nested {
+            nest(batch)
+        } else {
+            batch
+        }
+    }
+}
+
+/// Returns the seed of the RNG of the host `host_idx` of a generator seeded
+/// with `seed`
+///
+/// Both are mixed with SplitMix64, so e.g. host 1 of seed 0 and host 0 of
+/// seed 1 don't share a stream
+pub fn host_seed(seed: u64, host_idx: usize) -> u64 {
+    spike::splitmix64(spike::splitmix64(seed) ^ host_idx as u64)
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Iterator for Generator {
+    type Item = RecordBatch;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut rng = StdRng::seed_from_u64(host_seed(self.seed, self.host_idx));
+        let mut builder = BatchBuilder::new(Arc::clone(&self.config));
+        builder.append_host(&mut rng, self.host_idx);
+        self.host_idx += 1;
+        let batch = builder.finish_flat();
+        Some(self.finish_batch(batch))
+    }
+}
+
+/// The options of [`generate_batches`], the generation options of the
+/// command line other than those of [`GeneratorConfig`]
+#[derive(Debug, Clone)]
+pub struct Generation {
+    pub config: GeneratorConfig,
+    /// Seed of the generator, see [`Generator::with_seed`]
+    pub seed: u64,
+    /// Number of batches to generate, one per host
+    pub num_batches: usize,
+    /// Generate the batches with [`Generator::par_chunks`], which doesn't
+    /// change them
+    pub parallel: bool,
+    /// If set, stop once this many rows have been generated, truncating the
+    /// last batch
+    pub limit_total_rows: Option<usize>,
+}
+
+impl Default for Generation {
+    /// The defaults of the command line, 40 batches with a seed of 0
+    fn default() -> Self {
+        Self {
+            config: GeneratorConfig::default(),
+            seed: 0,
+            num_batches: 40,
+            parallel: false,
+            limit_total_rows: None,
+        }
+    }
+}
+
+/// Generates the batches of `generation` in memory, as the command line
+/// would write them
+///
+/// Batches are slices of the arrays they were built in, so truncating the
+/// last doesn't copy it
+pub fn generate_batches(generation: Generation) -> Vec<RecordBatch> {
+    let generator = Generator::with_seed(generation.seed).with_config(generation.config);
+    let mut batches = Vec::with_capacity(generation.num_batches);
+    let mut remaining = generation.limit_total_rows.unwrap_or(usize::MAX);
+    let generated: Box<dyn Iterator<Item = RecordBatch>> = if generation.parallel {
+        Box::new(generator.par_chunks(generation.num_batches))
+    } else {
+        Box::new(generator.take(generation.num_batches))
+    };
+    for batch in generated {
+        if remaining == 0 {
+            break;
+        }
+        let num_rows = batch.num_rows().min(remaining);
+        remaining -= num_rows;
+        batches.push(batch.slice(0, num_rows));
+    }
+    batches
+}
@@ -1,293 +1,2689 @@
-use arrow::array::{
-    Int32Builder, StringBuilder, StringDictionaryBuilder, TimestampMicrosecondBuilder,
-    TimestampNanosecondBuilder, UInt16Builder,
+use access_log_gen::{
+    next_part, write_clf, write_csv, write_ipc, write_ndjson, write_parquet,
+    write_parquet_flushing, write_parquet_partitioned, write_parquet_rolling, write_success_marker,
+    BatchBuilder, ByteCountType, Category, ColumnRanges, DatasetStats, Diurnal, Drift, Durations,
+    Generator, GeneratorConfig, IpVersion, PartitionBy, Routes, RowsSummary, SchemaDrift, Sizes,
+    Spikes, TimeUnitConfig, UserAgents, Weighted, DEFAULT_CONTAINER_TEMPLATE, DEFAULT_HOST_FORMAT,
+    DEFAULT_IMAGE_TEMPLATE, DEFAULT_METHOD_WEIGHTS, DEFAULT_PROTOCOL_WEIGHTS,
+    DEFAULT_REQUEST_BYTES_MEAN, DEFAULT_RESPONSE_BYTES_MEAN, DEFAULT_STATUS_WEIGHTS,
+    DEFAULT_USER_AGENT_WEIGHTS,
+};
+use anyhow::{anyhow, ensure, Context, Result};
+use arrow::array::{Array, ArrayRef, AsArray};
+use arrow::compute::{cast, max, min};
+use arrow::datatypes::{
+    DataType, Int64Type, Schema, SchemaRef, TimeUnit, TimestampMicrosecondType,
+    TimestampNanosecondType, TimestampSecondType,
 };
-use arrow::datatypes::{DataType, Field, Int32Type, Schema, SchemaRef, TimeUnit};
 use arrow::record_batch::RecordBatch;
+use arrow::util::display::array_value_to_string;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use flate2::write::GzEncoder;
+use flate2::Compression as GzCompression;
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
 use parquet::arrow::arrow_reader::{ArrowReaderOptions, ParquetRecordBatchReaderBuilder};
-use parquet::arrow::ArrowWriter;
-use parquet::file::properties::{EnabledStatistics, WriterProperties, WriterPropertiesBuilder};
+use parquet::arrow::ArrowSchemaConverter;
+use parquet::basic::{Compression, Encoding, GzipLevel, PageType, ZstdLevel};
+#[cfg(feature = "encryption")]
+use parquet::encryption::encrypt::FileEncryptionProperties;
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::{
+    EnabledStatistics, WriterProperties, WriterPropertiesBuilder, WriterVersion,
+    DEFAULT_DATA_PAGE_ROW_COUNT_LIMIT, DEFAULT_MAX_ROW_GROUP_SIZE, DEFAULT_PAGE_SIZE,
+};
 use parquet::file::reader::SerializedPageReader;
-use rand::rngs::StdRng;
-use rand::{Rng, SeedableRng};
+use parquet::format::SortingColumn;
+use parquet::schema::types::ColumnPath;
 use std::fs::File;
-use std::ops::Range;
-use std::sync::Arc;
-
-#[derive(Default)]
-struct BatchBuilder {
-    service: StringBuilder,
-    host: StringBuilder,
-    pod: StringBuilder,
-    container: StringBuilder,
-    image: StringBuilder,
-    time: TimestampMicrosecondBuilder,
-    client_addr: StringBuilder,
-    request_duration: Int32Builder,
-    request_user_agent: StringBuilder,
-    request_method: StringBuilder,
-    request_host: StringBuilder,
-    request_bytes: Int32Builder,
-    response_bytes: Int32Builder,
-    response_status: UInt16Builder,
-}
-
-impl BatchBuilder {
-    fn schema() -> SchemaRef {
-        // let utf8_dict =
-        //     || DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
-
-        Arc::new(Schema::new(vec![
-            Field::new("service", DataType::Utf8, true),
-            Field::new("host", DataType::Utf8, false),
-            Field::new("pod", DataType::Utf8, false),
-            Field::new("container", DataType::Utf8, false),
-            Field::new("image", DataType::Utf8, false),
-            Field::new(
-                "time",
-                DataType::Timestamp(TimeUnit::Microsecond, None),
-                false,
-            ),
-            Field::new("client_addr", DataType::Utf8, true),
-            Field::new("request_duration_ns", DataType::Int32, false),
-            Field::new("request_user_agent", DataType::Utf8, true),
-            Field::new("request_method", DataType::Utf8, true),
-            Field::new("request_host", DataType::Utf8, true),
-            Field::new("request_bytes", DataType::Int32, true),
-            Field::new("response_bytes", DataType::Int32, true),
-            Field::new("response_status", DataType::UInt16, false),
-        ]))
-    }
-
-    fn append(&mut self, rng: &mut StdRng, host: &str, service: &str) {
-        let num_pods = rng.gen_range(1..15);
-        let pods = generate_sorted_strings(rng, num_pods, 30..40);
-        for pod in pods {
-            for container_idx in 0..rng.gen_range(1..3) {
-                let container = format!("{}_container_{}", service, container_idx);
-                let image = format!(
-                    "{}@sha256:30375999bf03beec2187843017b10c9e88d8b1a91615df4eb6350fb39472edd9",
-                    container
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{mpsc, Arc};
+use std::time::Instant;
+
+/// Generates random parquet files with a schema approximating an access log
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// TOML file of option values keyed by their long name, e.g.
+    /// `num-batches = 100` or `services = ["frontend", "backend"]`, which
+    /// are overridden by options given on the command line
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    args: Args,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Read back a generated parquet file, printing every page and checking
+    /// it contains as many rows as the generation options produce
+    ///
+    /// Data pages must be of the --writer-version, and dictionary pages, of
+    /// which there must be some with --dictionary, encoded as it encodes
+    /// them and compressed with --compression. The footer must have the
+    /// metadata of --kv-metadata
+    Verify {
+        /// The parquet file to verify
+        path: PathBuf,
+    },
+    /// Generate the batches without writing them, printing the throughput of
+    /// the generator itself
+    Bench,
+}
+
+/// Options controlling the generated data and how it is written
+#[derive(Debug, Clone, clap::Args)]
+struct Args {
+    /// Number of batches to generate, one per host
+    #[arg(long, default_value_t = 40, value_parser = parse_positive)]
+    num_batches: usize,
+
+    /// RFC3339 start of a window the rows cover instead of --num-batches,
+    /// with --to, replacing --start-time
+    ///
+    /// Rows are spaced --interval-us apart on a single clock as with
+    /// --monotonic-time, or clustered as with --diurnal, and as many batches
+    /// are generated as the window takes. The number of rows is printed
+    #[arg(long, value_parser = parse_start_time, requires = "to")]
+    from: Option<i64>,
+
+    /// RFC3339 end of the window of --from, after the last row
+    #[arg(long, value_parser = parse_start_time, requires = "from")]
+    to: Option<i64>,
+
+    /// Stop once this many rows have been generated, truncating the last
+    /// batch, the output has fewer rows if --num-batches runs out first
+    #[arg(long, value_parser = parse_positive)]
+    limit_total_rows: Option<usize>,
+
+    /// Write each generated batch this many times, a cheap way to inflate
+    /// the output for I/O benchmarks at the cost of repeated rows
+    #[arg(long, default_value_t = 1, value_parser = parse_positive, conflicts_with = "from")]
+    repeat_batches: usize,
+
+    /// Microseconds added to the times of each repeat of a batch with
+    /// --repeat-batches over the previous one, so repeats don't share times
+    #[arg(long, default_value_t = 0, requires = "repeat_batches")]
+    repeat_shift_us: i64,
+
+    /// Generate batches in parallel, which doesn't change the output as each
+    /// host has its own RNG
+    #[arg(long)]
+    parallel: bool,
+
+    /// Number of distinct hosts, batches cycle through them rather than
+    /// each having a new host
+    #[arg(long, value_parser = parse_positive)]
+    num_hosts: Option<usize>,
+
+    /// Template of host names, where {n} is replaced with the index of the
+    /// host and {hex} with 16 hex digits derived from it, e.g. host-{n}
+    #[arg(long, default_value = DEFAULT_HOST_FORMAT, value_parser = parse_host_format)]
+    host_format: String,
+
+    /// Exact number of rows in each batch, defaults to a random number
+    #[arg(long, value_parser = parse_positive)]
+    rows_per_batch: Option<usize>,
+
+    /// RFC3339 timestamp of the first entry of each container
+    #[arg(long, default_value = "1970-01-01T00:00:00Z", value_parser = parse_start_time)]
+    start_time: i64,
+
+    /// Microseconds between consecutive entries of a container
+    #[arg(long, default_value_t = 1024, value_parser = clap::value_parser!(i64).range(0..))]
+    interval_us: i64,
+
+    /// Advance a single clock across every batch so the output is sorted by
+    /// time, rather than restarting from --start-time for every container
+    #[arg(long)]
+    monotonic_time: bool,
+
+    /// Cluster entries around --peak-hour and thin them out around the
+    /// opposite hour, rather than spacing them evenly, best combined with
+    /// --monotonic-time and an --interval-us spanning days
+    #[arg(long)]
+    diurnal: bool,
+
+    /// Hour of the day, in UTC, with the most entries with --diurnal, e.g.
+    /// 14.5 for 14:30
+    #[arg(long, default_value_t = 14., requires = "diurnal")]
+    peak_hour: f64,
+
+    /// How strongly entries cluster with --diurnal, from 0 for evenly spaced
+    /// to 1 for none at the opposite hour
+    #[arg(long, default_value_t = 0.8, requires = "diurnal")]
+    diurnal_amplitude: f64,
+
+    /// Unit of the time column, one of micros or nanos
+    #[arg(long, default_value = "micros")]
+    time_unit: TimeUnitConfig,
+
+    /// IANA timezone to store in the time column's type, e.g. UTC or
+    /// America/New_York, the values themselves are unchanged
+    #[arg(long, value_parser = parse_timezone)]
+    timezone: Option<String>,
+
+    /// Comma-separated list of services that may run on each host
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_value = "frontend,backend,database,cache",
+        value_parser = parse_service
+    )]
+    services: Vec<String>,
+
+    /// Probability that a given service runs on a host
+    #[arg(long, default_value_t = 0.5, value_parser = parse_probability)]
+    service_probability: f64,
+
+    /// Range of the number of pods of each service on a host, as MIN..MAX
+    #[arg(long, default_value = "1..14", value_parser = parse_count_range)]
+    pods_per_service: RangeInclusive<usize>,
+
+    /// Range of the number of containers of each pod, as MIN..MAX
+    #[arg(long, default_value = "1..2", value_parser = parse_count_range)]
+    containers_per_pod: RangeInclusive<usize>,
+
+    /// Range of the length of pod names, as MIN..MAX
+    #[arg(long, default_value = "30..39", value_parser = parse_count_range)]
+    pod_name_len: RangeInclusive<usize>,
+
+    /// Leave the pods of each service on a host in random order rather than
+    /// sorted by name, widening the min and max of the pod column
+    #[arg(long)]
+    no_sort_pods: bool,
+
+    /// Template of container names, where {service} is replaced with the
+    /// service and {idx} with the index of the container in its pod, e.g.
+    /// {service}-{idx}
+    #[arg(
+        long,
+        default_value = DEFAULT_CONTAINER_TEMPLATE,
+        value_parser = parse_container_template
+    )]
+    container_template: String,
+
+    /// Template of container images, with the placeholders of
+    /// --container-template and {digest}, e.g.
+    /// registry.example.com/{service}@sha256:{digest}
+    #[arg(long, default_value = DEFAULT_IMAGE_TEMPLATE)]
+    image_template: String,
+
+    /// Give every container image a random digest rather than the same fixed
+    /// one, raising the cardinality of the image column
+    #[arg(long)]
+    unique_digests: bool,
+
+    /// Comma-separated code:weight pairs for the response status distribution
+    #[arg(long, default_value = DEFAULT_STATUS_WEIGHTS)]
+    status_weights: Weighted<u16>,
+
+    /// Make the response_status column nullable, with 1% of statuses null
+    /// unless set with --null-rate response_status:RATE
+    #[arg(long)]
+    allow_null_status: bool,
+
+    /// Comma-separated method:weight pairs for the request method
+    /// distribution, e.g. GET:0.7,POST:0.2,PUT:0.1
+    #[arg(long, default_value = DEFAULT_METHOD_WEIGHTS)]
+    method_weights: Weighted<String>,
+
+    /// Enable dictionary encoding, building the repetitive string columns
+    /// as dictionary arrays
+    #[arg(long)]
+    dictionary: bool,
+
+    /// Address family of client addresses, one of v4, v6 or mixed
+    #[arg(long, default_value = "v4")]
+    ip_version: IpVersion,
+
+    /// Fraction of IPv6 client addresses with --ip-version mixed
+    #[arg(long, default_value_t = 0.5, value_parser = parse_probability)]
+    ipv6_ratio: f64,
+
+    /// Draw client addresses from a curated set of public address blocks
+    #[arg(long)]
+    realistic_ips: bool,
+
+    /// Mean request duration in milliseconds
+    #[arg(long, default_value_t = 50.)]
+    duration_mean_ms: f64,
+
+    /// 99th percentile request duration in milliseconds, at most about 15
+    /// times --duration-mean-ms
+    ///
+    /// Durations are written in nanoseconds to request_duration_ns
+    #[arg(long, default_value_t = 500.)]
+    duration_p99_ms: f64,
+
+    /// Add a request_duration_ms column of the durations in whole
+    /// milliseconds
+    #[arg(long)]
+    with_duration_ms: bool,
+
+    /// Add an upstream_duration_ns column of the time spent in the backend,
+    /// 80% to 100% of request_duration_ns, the rest being proxy overhead
+    #[arg(long)]
+    with_upstream_time: bool,
+
+    /// Type of the request_bytes and response_bytes columns, one of int32,
+    /// int64 or decimal, int32 caps sizes at 2GiB
+    #[arg(long, default_value = "int32")]
+    byte_count_type: ByteCountType,
+
+    /// Mean size of request bodies in bytes, most are smaller
+    #[arg(long, default_value_t = DEFAULT_REQUEST_BYTES_MEAN)]
+    request_bytes_mean: f64,
+
+    /// Mean size of response bodies in bytes, with a heavy tail of larger
+    /// ones, ignored with --correlate-status
+    #[arg(long, default_value_t = DEFAULT_RESPONSE_BYTES_MEAN)]
+    response_bytes_mean: f64,
+
+    /// Derive request durations and response sizes from the response status,
+    /// e.g. 5xx responses are fast with small bodies
+    #[arg(long)]
+    correlate_status: bool,
+
+    /// Inject short windows where most responses are 503s or requests are
+    /// much slower, listed in manifest.json with --manifest
+    #[arg(long)]
+    inject_spikes: bool,
+
+    /// Average number of spikes an hour with --inject-spikes
+    #[arg(long, default_value_t = 1., requires = "inject_spikes")]
+    spike_frequency: f64,
+
+    /// Length of each spike in seconds with --inject-spikes
+    #[arg(long, default_value_t = 60., requires = "inject_spikes")]
+    spike_duration_s: f64,
+
+    /// How severe spikes are with --inject-spikes, requests take this many
+    /// times longer in latency spikes, and all but 1 in this many responses
+    /// are 503s in error spikes
+    #[arg(long, default_value_t = 10., requires = "inject_spikes")]
+    spike_magnitude: f64,
+
+    /// Add a geo_country column derived from the client address block
+    #[arg(long, requires = "realistic_ips")]
+    with_geo_country: bool,
+
+    /// Comma-separated category:weight pairs for the built-in user agents,
+    /// the categories are desktop, mobile, bot and cli
+    #[arg(long, default_value = DEFAULT_USER_AGENT_WEIGHTS)]
+    user_agent_weights: Weighted<Category>,
+
+    /// Newline-delimited file of user agents to sample from instead of the
+    /// built-in templates
+    #[arg(long, conflicts_with = "random_user_agents")]
+    user_agents_file: Option<PathBuf>,
+
+    /// Generate user agents as random lowercase strings
+    #[arg(long)]
+    random_user_agents: bool,
+
+    /// Range of the length of user agents with --random-user-agents, as
+    /// MIN..MAX
+    #[arg(
+        long,
+        default_value = "20..99",
+        value_parser = parse_count_range,
+        requires = "random_user_agents"
+    )]
+    user_agent_len: RangeInclusive<usize>,
+
+    /// File of request path templates, one "<service> <path>" per line
+    ///
+    /// A service of "*" applies to services without routes of their own,
+    /// {id} and {slug} in a path are replaced with random values
+    #[arg(long)]
+    routes_file: Option<PathBuf>,
+
+    /// Add a request_query column with random query strings
+    #[arg(long)]
+    with_query: bool,
+
+    /// Add a request_protocol column of HTTP versions, which the clf format
+    /// writes in the request line
+    #[arg(long)]
+    with_protocol: bool,
+
+    /// Comma-separated protocol:weight pairs for the request_protocol
+    /// distribution with --with-protocol, e.g. HTTP/1.1:1,HTTP/2:1
+    #[arg(long, default_value = DEFAULT_PROTOCOL_WEIGHTS, requires = "with_protocol")]
+    protocol_weights: Weighted<String>,
+
+    /// Add a headers column listing random request headers
+    #[arg(long)]
+    with_headers: bool,
+
+    /// Range of the number of headers of each request with --with-headers,
+    /// as MIN..MAX, rows are also null at the headers --null-rate, 0.05 by
+    /// default
+    #[arg(long, default_value = "0..4", value_parser = parse_range, requires = "with_headers")]
+    headers_per_row: RangeInclusive<usize>,
+
+    /// Add a query_params column mapping up to 3 random query parameter
+    /// names to values, possibly none
+    #[arg(long)]
+    with_query_params: bool,
+
+    /// Add a service_tier column of edge, app or data derived from the
+    /// service name, e.g. edge for frontend and data for database
+    #[arg(long)]
+    with_service_tier: bool,
+
+    /// Add a level column of log levels following the response status,
+    /// ERROR for 5xx, WARN for 4xx, and otherwise mostly INFO with some
+    /// DEBUG
+    #[arg(long)]
+    with_level: bool,
+
+    /// Add trace_id and span_id columns, where requests to the same pod
+    /// within a second occasionally share a trace
+    #[arg(long)]
+    with_tracing: bool,
+
+    /// Add a schema_version column with this value in every row, e.g. to
+    /// generate files of several versions for schema evolution tests
+    #[arg(long)]
+    schema_version: Option<i32>,
+
+    /// Add a _batch_id column with the index of the batch each row was
+    /// generated in, for debugging partitioning and --shuffle
+    #[arg(long)]
+    with_batch_id: bool,
+
+    /// Add a session_id column with sessions of this many requests on average
+    #[arg(long, value_parser = parse_positive)]
+    session_length: Option<usize>,
+
+    /// Fraction of requests without a referer
+    #[arg(long, default_value_t = 0.3, value_parser = parse_probability)]
+    referer_null_rate: f64,
+
+    /// Fraction of referers from the same service rather than external sites
+    #[arg(long, default_value_t = 0.6, value_parser = parse_probability)]
+    referer_internal_ratio: f64,
+
+    /// Replace every random value with a fixed one, keeping the configured
+    /// hosts, services and batch sizes, see the library docs for the values
+    #[arg(long)]
+    deterministic: bool,
+
+    /// Comma-separated columns to generate, e.g. time,host,response_status,
+    /// defaults to every column
+    #[arg(long, value_delimiter = ',')]
+    columns: Vec<String>,
+
+    /// Group the request_* and response_* columns into request and response
+    /// struct columns, e.g. request.method, other options still take the
+    /// flat names, not supported by --format csv or clf
+    #[arg(long)]
+    nested: bool,
+
+    /// Randomly permute the rows of each batch, seeded by --seed, so row
+    /// group statistics overlap and can't prune anything
+    #[arg(long)]
+    shuffle: bool,
+
+    /// Comma-separated `column:rate` null probabilities of nullable columns,
+    /// e.g. `request_bytes:0.1,client_addr:0.05`
+    #[arg(long, value_delimiter = ',', value_parser = parse_null_rate)]
+    null_rate: Vec<(String, f64)>,
+
+    /// Seed for the random number generator, defaults to a fixed seed
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Directory to write the output files to, created if it doesn't exist
+    ///
+    /// If "-" a single output file is written to stdout, for parquet this
+    /// is the page-stats variant
+    #[arg(long, default_value = ".")]
+    out_dir: PathBuf,
+
+    /// Compression codec, one of none, snappy, gzip, lz4, lz4_raw, zstd or
+    /// brotli
+    ///
+    /// gzip and zstd accept an optional level, e.g. zstd:9. lz4 is the
+    /// deprecated Hadoop framed codec and lz4_raw the plain block codec, and
+    /// readers often support only one of them
+    #[arg(long, default_value = "none", value_parser = parse_compression)]
+    compression: Compression,
+
+    /// Output format to write
+    #[arg(long, value_enum, default_value_t = Format::Parquet)]
+    format: Format,
+
+    /// Comma-separated parquet files to write, of no-stats, chunk-stats,
+    /// page-stats and bloom, defaults to all of them, or page-stats when
+    /// writing to stdout
+    #[arg(long, value_enum, value_delimiter = ',')]
+    variants: Option<Vec<Variant>>,
+
+    /// Also write logs-seconds.parquet, like logs-page-stats.parquet but with
+    /// time truncated to whole seconds as a TimeUnit::Second timestamp
+    #[arg(long)]
+    emit_seconds_variant: bool,
+
+    /// Omit null values from NDJSON output instead of writing `null`
+    #[arg(long)]
+    json_omit_nulls: bool,
+
+    /// Name of the Common Log Format file, gzip compressed if it ends in .gz
+    #[arg(long, default_value = "logs.log")]
+    clf_file: String,
+
+    /// Write Combined Log Format lines, adding the referer and user agent
+    #[arg(long)]
+    clf_combined: bool,
+
+    /// Comma-separated columns with bloom filters in logs-bloom.parquet,
+    /// defaults to those of client_addr and request_user_agent that are
+    /// generated
+    #[arg(long, value_delimiter = ',')]
+    bloom_columns: Option<Vec<String>>,
+
+    /// Maximum number of rows in each parquet row group
+    #[arg(long, default_value_t = DEFAULT_MAX_ROW_GROUP_SIZE, value_parser = parse_positive)]
+    row_group_size: usize,
+
+    /// Close a parquet row group after every N rows, slicing batches at the
+    /// boundaries, so row groups and their statistics cover fixed row
+    /// ranges however --rows-per-batch varies
+    ///
+    /// A smaller --row-group-size still splits row groups in between. Chunk
+    /// statistics and bloom filters are per row group, so with
+    /// --monotonic-time the time range of row group i is that of rows i*N to
+    /// (i+1)*N, and pages never span the boundaries
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = parse_positive,
+        conflicts_with_all = ["partition_by", "layout", "target_file_size", "append"]
+    )]
+    flush_every: Option<usize>,
+
+    /// Parquet format version of the written files, v1 or v2, where v2
+    /// writes data page v2 headers and encodings
+    #[arg(long, default_value = "v1", value_parser = parse_writer_version)]
+    writer_version: WriterVersion,
+
+    /// Best-effort limit on the size of each parquet data page, e.g. 8KB
+    ///
+    /// Pages never span row groups, so each row group also ends its pages,
+    /// and limits are only checked every 1024 rows, so pages hold at least
+    /// that many rows unless a row group is smaller
+    #[arg(long, default_value_t = DEFAULT_PAGE_SIZE, value_parser = parse_size)]
+    data_page_size: usize,
+
+    /// Best-effort limit on the number of rows of each parquet data page,
+    /// subject to the same checks as --data-page-size
+    #[arg(long, default_value_t = DEFAULT_DATA_PAGE_ROW_COUNT_LIMIT, value_parser = parse_positive)]
+    data_page_row_count: usize,
+
+    /// Force the parquet encoding of a column as column:ENCODING, e.g.
+    /// request_duration_ns:DELTA_BINARY_PACKED, disabling its dictionary
+    #[arg(long, value_parser = parse_column_encoding)]
+    column_encoding: Vec<(String, Encoding)>,
+
+    /// Override the statistics of a column in every parquet file as
+    /// column:page, column:chunk or column:none
+    #[arg(long, value_parser = parse_column_statistics)]
+    stats: Vec<(String, EnabledStatistics)>,
+
+    /// Write the parquet files without a column index or offset index, so
+    /// readers can't skip pages
+    ///
+    /// The column index holds the page statistics, so page statistics,
+    /// e.g. of logs-page-stats.parquet or --stats column:page, are
+    /// written as chunk statistics instead
+    #[arg(long)]
+    no_page_index: bool,
+
+    /// Add a key=value entry to the footer metadata of the parquet files,
+    /// which also record the access_log_gen.version and access_log_gen.seed
+    /// they were generated with, unless overridden
+    #[arg(long, value_parser = parse_key_value)]
+    kv_metadata: Vec<(String, String)>,
+
+    /// Comma-separated columns the parquet files declare their rows sorted
+    /// by in ascending order, e.g. host,time, which is not checked against
+    /// the data
+    #[arg(long, value_delimiter = ',')]
+    sort_by: Vec<String>,
+
+    /// Write each parquet file as a directory of Hive-style partitions, e.g.
+    /// logs-page-stats/host=.../data.parquet, one of host, service or date,
+    /// where dates are in the --timezone
+    #[arg(long)]
+    partition_by: Option<PartitionBy>,
+
+    /// Write each parquet file as a single file, the default, or as one file
+    /// per host or per service like --partition-by host or service
+    #[arg(
+        long,
+        conflicts_with_all = ["partition_by", "target_file_size", "append"]
+    )]
+    layout: Option<Layout>,
+
+    /// Omit the partition column from partitioned files, as it is encoded
+    /// in their path
+    #[arg(long)]
+    drop_partition_column: bool,
+
+    /// Write each parquet file as a directory of part files of roughly this
+    /// size, e.g. 128MB, accepting B, KB, MB and GB in powers of 1024
+    #[arg(long, value_parser = parse_size, conflicts_with = "partition_by")]
+    target_file_size: Option<usize>,
+
+    /// Add the parquet files as new part files of the directories previous
+    /// runs wrote, e.g. logs-page-stats/part-0003.parquet, checking their
+    /// schema matches, use a different --seed for new data
+    #[arg(long, conflicts_with = "partition_by")]
+    append: bool,
+
+    /// Once the directories of --partition-by, --target-file-size or --append
+    /// are complete, write a _metadata summary of their row groups and an
+    /// empty _SUCCESS marker to each, as Spark-style readers expect
+    #[arg(long)]
+    success_marker: bool,
+
+    /// Vary the schema of the part files of --target-file-size, where the
+    /// second part drops client_addr, the third adds a trace_id column, the
+    /// fourth moves time first and the fifth makes every column nullable,
+    /// repeating from the unchanged first part after that
+    #[arg(long, requires = "target_file_size")]
+    schema_drift: bool,
+
+    /// Encrypt the parquet files with Parquet modular encryption and the
+    /// --encryption-key, requires the encryption feature
+    ///
+    /// The footer is always encrypted, and without --encrypt-columns so is
+    /// every column, as uniform encryption. Readers need the same key, e.g.
+    /// as the key of the footer and of every encrypted column
+    #[arg(long)]
+    encrypt: bool,
+
+    /// Comma-separated columns to encrypt with --encrypt, leaving the others
+    /// as plaintext
+    #[arg(long, value_delimiter = ',', requires = "encrypt")]
+    encrypt_columns: Vec<String>,
+
+    /// AES-128 key of --encrypt as 32 hex digits, used for the footer and
+    /// every encrypted column
+    ///
+    /// A key on the command line is visible to other users in the process
+    /// list and kept in the shell history, so prefer `encryption-key` in a
+    /// --config file readable only by you. The key is never printed or
+    /// written to the manifest
+    #[cfg(feature = "encryption")]
+    #[arg(long, value_parser = parse_encryption_key)]
+    encryption_key: Option<EncryptionKey>,
+
+    /// Write every output into this archive in --out-dir instead of as loose
+    /// files, keeping their directories, as a .zip, .tar, .tar.gz or .tgz
+    /// file, requires the archive feature
+    #[arg(long, value_parser = parse_archive, conflicts_with = "append")]
+    archive: Option<String>,
+
+    /// Also write a manifest.json describing every column, with the min, max
+    /// and null count of its values, and the parameters the data was
+    /// generated with
+    #[arg(long)]
+    manifest: bool,
+
+    /// Print the number of rows, estimated uncompressed size and cardinality
+    /// of each column instead of writing any file, as text or json
+    #[arg(
+        long,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "text"
+    )]
+    dry_run: Option<Report>,
+
+    /// Once the files are written, print the number of rows of each service
+    /// and response status, and the first and last time, as text or json
+    #[arg(
+        long,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "text"
+    )]
+    rows_summary: Option<Report>,
+
+    /// Report the batches and rows written to each file on stderr, as a
+    /// progress bar on a terminal and periodic log lines otherwise
+    #[arg(long)]
+    progress: bool,
+
+    /// Don't print the files written and other messages, only errors
+    #[arg(long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Log each step on stderr as `key=value` fields, e.g. the rows and
+    /// time of every batch and the bytes of every file written, overridden
+    /// by `RUST_LOG`
+    #[arg(long)]
+    verbose: bool,
+}
+
+/// A key of --encrypt, which isn't printed by `Debug`
+#[cfg(feature = "encryption")]
+#[derive(Clone)]
+struct EncryptionKey(Vec<u8>);
+
+#[cfg(feature = "encryption")]
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// The logs-{no,chunk,page}-stats.parquet and logs-bloom.parquet files
+    Parquet,
+    /// A single logs.csv file
+    Csv,
+    /// A single newline-delimited JSON logs.ndjson file
+    Ndjson,
+    /// A single Arrow IPC logs.arrow file
+    Ipc,
+    /// A single Common Log Format file named by --clf-file
+    Clf,
+    /// A single logs.orc file, requires the orc feature
+    Orc,
+    /// Every supported format
+    All,
+}
+
+impl Args {
+    fn config(&self) -> Result<GeneratorConfig> {
+        // Checked here as well as by clap, which skips defaults set by
+        // --config when checking requirements
+        ensure!(
+            !self.with_geo_country || self.realistic_ips,
+            "--with-geo-country requires --realistic-ips"
+        );
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            ensure!(to > from, "--to must be after --from");
+            ensure!(
+                self.interval_us > 0,
+                "--from and --to require a positive --interval-us"
+            );
+        }
+
+        let user_agents = match &self.user_agents_file {
+            _ if self.random_user_agents => UserAgents::Random(self.user_agent_len.clone()),
+            Some(path) => {
+                let agents: Vec<_> = std::fs::read_to_string(path)
+                    .with_context(|| format!("failed to read {}", path.display()))?
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(String::from)
+                    .collect();
+                ensure!(
+                    !agents.is_empty(),
+                    "{} contains no user agents",
+                    path.display()
+                );
+                UserAgents::Custom(agents)
+            }
+            None => UserAgents::Builtin(self.user_agent_weights.clone()),
+        };
+
+        let routes = match &self.routes_file {
+            Some(path) => {
+                let routes = std::fs::read_to_string(path)
+                    .with_context(|| format!("failed to read {}", path.display()))?;
+                Routes::parse(&routes)
+                    .map_err(|e| anyhow!("invalid routes file {}: {}", path.display(), e))?
+            }
+            None => Routes::default(),
+        };
+
+        let diurnal = self
+            .diurnal
+            .then(|| Diurnal::new(self.peak_hour, self.diurnal_amplitude))
+            .transpose()
+            .map_err(|e| anyhow!("invalid diurnal pattern: {}", e))?;
+
+        let durations = Durations::new(self.duration_mean_ms, self.duration_p99_ms)
+            .map_err(|e| anyhow!("invalid request durations: {}", e))?;
+        let request_sizes = Sizes::requests(self.request_bytes_mean)
+            .map_err(|e| anyhow!("invalid request sizes: {}", e))?;
+        let response_sizes = Sizes::responses(self.response_bytes_mean)
+            .map_err(|e| anyhow!("invalid response sizes: {}", e))?;
+        let spikes = self
+            .inject_spikes
+            .then(|| {
+                Spikes::new(
+                    self.seed.unwrap_or_default(),
+                    self.spike_frequency,
+                    (self.spike_duration_s * 1e6) as i64,
+                    self.spike_magnitude,
+                )
+            })
+            .transpose()
+            .map_err(|e| anyhow!("invalid spikes: {}", e))?;
+
+        let mut config = GeneratorConfig {
+            rows_per_batch: self.rows_per_batch,
+            start_time: self.from.unwrap_or(self.start_time),
+            interval_us: self.interval_us,
+            monotonic_time: self.monotonic_time || self.from.is_some(),
+            diurnal,
+            time_unit: self.time_unit,
+            timezone: self.timezone.clone(),
+            num_hosts: self.num_hosts,
+            host_format: self.host_format.clone(),
+            container_template: self.container_template.clone(),
+            image_template: self.image_template.clone(),
+            services: self.services.clone(),
+            service_probability: self.service_probability,
+            pods_per_service: self.pods_per_service.clone(),
+            containers_per_pod: self.containers_per_pod.clone(),
+            pod_name_len: self.pod_name_len.clone(),
+            sort_pods: !self.no_sort_pods,
+            unique_digests: self.unique_digests,
+            status_weights: self.status_weights.clone(),
+            nullable_status: self.allow_null_status,
+            method_weights: self.method_weights.clone(),
+            dictionary: self.dictionary,
+            ip_version: self.ip_version,
+            ipv6_ratio: self.ipv6_ratio,
+            realistic_ips: self.realistic_ips,
+            geo_country: self.with_geo_country,
+            durations,
+            duration_ms: self.with_duration_ms,
+            upstream_time: self.with_upstream_time,
+            byte_count_type: self.byte_count_type,
+            request_sizes,
+            response_sizes,
+            spikes,
+            correlate_status: self.correlate_status,
+            user_agents,
+            routes,
+            request_query: self.with_query,
+            protocols: self.with_protocol.then(|| self.protocol_weights.clone()),
+            session_length: self.session_length,
+            headers: self.with_headers.then(|| self.headers_per_row.clone()),
+            query_params: self.with_query_params,
+            service_tier: self.with_service_tier,
+            level: self.with_level,
+            tracing: self.with_tracing,
+            schema_version: self.schema_version,
+            batch_id: self.with_batch_id,
+            referer_null_rate: self.referer_null_rate,
+            referer_internal_ratio: self.referer_internal_ratio,
+            null_rates: self.null_rate.iter().cloned().collect(),
+            deterministic: self.deterministic,
+            columns: None,
+            nested: self.nested,
+            shuffle: self.shuffle,
+        };
+        if !self.columns.is_empty() {
+            let schema = BatchBuilder::flat_schema(&config);
+            for column in &self.columns {
+                ensure!(
+                    schema.field_with_name(column).is_ok(),
+                    "unknown column \"{}\" in --columns",
+                    column
                 );
+            }
+            config.columns = Some(self.columns.clone());
+        }
 
-                let num_entries = rng.gen_range(1024..8192);
-                for i in 0..num_entries {
-                    let time = i as i64 * 1024;
-                    self.append_row(rng, host, &pod, service, &container, &image, time);
+        // Checked here as clap doesn't know the schema, reported like clap's
+        // errors before any output is created. Column options use the flat
+        // names even with --nested
+        let flat_schema = BatchBuilder::flat_schema(&config);
+        for column in self.bloom_columns.iter().flatten() {
+            if flat_schema.field_with_name(column).is_err() {
+                Cli::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!("unknown column \"{}\" in --bloom-columns", column),
+                    )
+                    .exit();
+            }
+        }
+
+        for (column, _) in &self.stats {
+            if flat_schema.field_with_name(column).is_err() {
+                Cli::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!("unknown column \"{}\" in --stats", column),
+                    )
+                    .exit();
+            }
+        }
+
+        for (column, encoding) in &self.column_encoding {
+            let field = match flat_schema.field_with_name(column) {
+                Ok(field) => field,
+                Err(_) => Cli::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!("unknown column \"{}\" in --column-encoding", column),
+                    )
+                    .exit(),
+            };
+            if !encoding_supported(field.data_type(), *encoding) {
+                Cli::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!(
+                            "{} encoding is not supported by column \"{}\" of type {}",
+                            encoding,
+                            column,
+                            field.data_type()
+                        ),
+                    )
+                    .exit();
+            }
+        }
+
+        for column in &self.encrypt_columns {
+            if flat_schema.field_with_name(column).is_err() {
+                Cli::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!("unknown column \"{}\" in --encrypt-columns", column),
+                    )
+                    .exit();
+            }
+        }
+
+        for (column, _) in &self.null_rate {
+            match flat_schema.field_with_name(column) {
+                Ok(field) if field.is_nullable() => {}
+                Ok(_) => Cli::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!("column \"{}\" in --null-rate is not nullable", column),
+                    )
+                    .exit(),
+                Err(_) => Cli::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!("unknown column \"{}\" in --null-rate", column),
+                    )
+                    .exit(),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Returns the parquet path of the flat `column`, e.g. `request.method`
+    /// for `request_method` with --nested
+    fn column_path(&self, column: &str) -> ColumnPath {
+        let nested = ["request", "response"].into_iter().find_map(|parent| {
+            let child = column.strip_prefix(parent)?.strip_prefix('_')?;
+            Some(vec![parent.to_string(), child.to_string()])
+        });
+        match nested {
+            Some(path) if self.nested => ColumnPath::new(path),
+            _ => ColumnPath::from(column),
+        }
+    }
+
+    /// Returns the properties of --encrypt for files of `schema`, where each
+    /// of --encrypt-columns encrypts all its leaf columns, e.g.
+    /// `headers.list.item`
+    #[cfg(feature = "encryption")]
+    fn encryption_properties(&self, schema: &Schema) -> Result<FileEncryptionProperties> {
+        let key = &self
+            .encryption_key
+            .as_ref()
+            .expect("--encrypt requires --encryption-key")
+            .0;
+        let mut builder = FileEncryptionProperties::builder(key.clone());
+        let descriptor = ArrowSchemaConverter::new().convert(schema)?;
+        for column in &self.encrypt_columns {
+            let column = self.column_path(column);
+            for leaf in descriptor.columns() {
+                if leaf.path().parts().starts_with(column.parts()) {
+                    builder = builder.with_column_key(&leaf.path().string(), key.clone());
                 }
             }
         }
+        Ok(builder.build()?)
     }
 
-    fn append_row(
-        &mut self,
-        rng: &mut StdRng,
-        host: &str,
-        pod: &str,
-        service: &str,
-        container: &str,
-        image: &str,
-        time: i64,
-    ) {
-        let methods = &["GET", "PUT", "POST", "HEAD", "PATCH", "DELETE"];
-        let status = &[200, 204, 400, 503, 403];
-
-        self.service.append_value(service);
-        self.host.append_value(host);
-        self.pod.append_value(pod);
-        self.container.append_value(container);
-        self.image.append_value(image);
-        self.time.append_value(time);
-
-        self.client_addr.append_value(format!(
-            "{}.{}.{}.{}",
-            rng.gen::<u8>(),
-            rng.gen::<u8>(),
-            rng.gen::<u8>(),
-            rng.gen::<u8>()
-        ));
-        self.request_duration.append_value(rng.gen());
-        self.request_user_agent
-            .append_value(random_string(rng, 20..100));
-        self.request_method
-            .append_value(methods[rng.gen_range(0..methods.len())]);
-        self.request_host
-            .append_value(format!("https://{}.mydomain.com", service));
-
-        self.request_bytes
-            .append_option(rng.gen_bool(0.9).then(|| rng.gen()));
-        self.response_bytes
-            .append_option(rng.gen_bool(0.9).then(|| rng.gen()));
-        self.response_status
-            .append_value(status[rng.gen_range(0..status.len())]);
-    }
-
-    fn finish(mut self, schema: SchemaRef) -> RecordBatch {
-        RecordBatch::try_new(
-            schema,
-            vec![
-                Arc::new(self.service.finish()),
-                Arc::new(self.host.finish()),
-                Arc::new(self.pod.finish()),
-                Arc::new(self.container.finish()),
-                Arc::new(self.image.finish()),
-                Arc::new(self.time.finish()),
-                Arc::new(self.client_addr.finish()),
-                Arc::new(self.request_duration.finish()),
-                Arc::new(self.request_user_agent.finish()),
-                Arc::new(self.request_method.finish()),
-                Arc::new(self.request_host.finish()),
-                Arc::new(self.request_bytes.finish()),
-                Arc::new(self.response_bytes.finish()),
-                Arc::new(self.response_status.finish()),
-            ],
-        )
-        .unwrap()
+    /// Returns the footer metadata of the parquet files, the generator
+    /// version and seed followed by --kv-metadata, which replaces them
+    fn key_value_metadata(&self) -> Vec<KeyValue> {
+        let generated = [
+            (
+                "access_log_gen.version",
+                env!("CARGO_PKG_VERSION").to_string(),
+            ),
+            (
+                "access_log_gen.seed",
+                self.seed.unwrap_or_default().to_string(),
+            ),
+        ];
+        generated
+            .into_iter()
+            .filter(|(key, _)| !self.kv_metadata.iter().any(|(k, _)| k == key))
+            .map(|(key, value)| KeyValue::new(key.to_string(), value))
+            .chain(
+                self.kv_metadata
+                    .iter()
+                    .map(|(key, value)| KeyValue::new(key.clone(), value.clone())),
+            )
+            .collect()
+    }
+
+    /// Returns the number of rows in the window of --from and --to, the
+    /// last of which is before --to
+    fn window_rows(&self) -> Option<usize> {
+        let (from, to) = (self.from?, self.to?);
+        let rows = (to - from + self.interval_us - 1) / self.interval_us;
+        Some(rows as usize)
+    }
+
+    /// Returns --bloom-columns, or the default columns of them in `schema`,
+    /// which --columns may leave out
+    fn bloom_columns(&self, schema: &Schema) -> Vec<String> {
+        match &self.bloom_columns {
+            Some(columns) => columns.clone(),
+            None => ["client_addr", "request_user_agent"]
+                .into_iter()
+                .filter(|column| schema.field_with_name(column).is_ok())
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    /// Returns the number of rows the generation stops at, given a window or
+    /// --limit-total-rows
+    fn row_limit(&self) -> Option<usize> {
+        match (self.window_rows(), self.limit_total_rows) {
+            (Some(rows), Some(limit)) => Some(rows.min(limit)),
+            (rows, limit) => rows.or(limit),
+        }
+    }
+
+    /// Returns a new iterator of the batches to write
+    ///
+    /// Each output file regenerates the batches from the same seed, trading
+    /// generation time for not holding the whole dataset in memory, except the
+    /// parquet variants which are written concurrently from one generation
+    fn batches(&self, config: &GeneratorConfig) -> Box<dyn Iterator<Item = RecordBatch>> {
+        let generator = match self.seed {
+            Some(seed) => Generator::with_seed(seed),
+            None => Generator::new(),
+        }
+        .with_config(config.clone());
+
+        // A window takes as many batches as it has rows for
+        let num_batches = match self.window_rows() {
+            Some(_) => usize::MAX,
+            None => self.num_batches,
+        };
+        let batches: Box<dyn Iterator<Item = RecordBatch>> = if self.parallel {
+            Box::new(generator.par_chunks(num_batches))
+        } else {
+            Box::new(generator.take(num_batches))
+        };
+        let (repeat, shift_us) = (self.repeat_batches, self.repeat_shift_us);
+        let batches: Box<dyn Iterator<Item = RecordBatch>> = if repeat > 1 {
+            Box::new(batches.flat_map(move |batch| {
+                (0..repeat).map(move |i| shift_time(batch.clone(), i as i64 * shift_us))
+            }))
+        } else {
+            batches
+        };
+        let Some(limit) = self.row_limit() else {
+            return batches;
+        };
+        let mut remaining = limit;
+        Box::new(batches.map_while(move |batch| {
+            if remaining == 0 {
+                return None;
+            }
+            let num_rows = batch.num_rows().min(remaining);
+            remaining -= num_rows;
+            Some(batch.slice(0, num_rows))
+        }))
+    }
+}
+
+impl Args {
+    /// Returns true if the output is written to stdout rather than `out_dir`
+    fn to_stdout(&self) -> bool {
+        self.out_dir.as_os_str() == "-"
+    }
+
+    /// Returns the path of the output file `name`, or "-" for stdout
+    fn path(&self, name: &str) -> PathBuf {
+        if self.to_stdout() {
+            PathBuf::from("-")
+        } else {
+            self.out_dir.join(name)
+        }
+    }
+
+    /// Returns the partitions of --partition-by or --layout
+    fn partition_by(&self) -> Option<PartitionBy> {
+        self.partition_by
+            .or_else(|| self.layout.and_then(Layout::partition_by))
+    }
+
+    /// Returns true if each parquet file is written as a directory of files
+    fn multi_file(&self) -> bool {
+        self.partition_by().is_some() || self.target_file_size.is_some() || self.append
+    }
+
+    /// Returns the parquet files to write
+    fn variants(&self) -> Vec<Variant> {
+        match &self.variants {
+            Some(variants) => variants.clone(),
+            None if self.to_stdout() => vec![Variant::PageStats],
+            None => Variant::value_variants().to_vec(),
+        }
+    }
+
+    /// Returns an error if an output file would replace a directory, or an
+    /// output directory of --partition-by, --target-file-size or --append is
+    /// a file, so no output is written if any of them would fail
+    fn check_outputs(&self) -> Result<()> {
+        let mut files = Vec::new();
+        let mut dirs = Vec::new();
+        if self.format.includes(Format::Parquet) {
+            for variant in self.variants() {
+                let name = variant.file_name();
+                if self.multi_file() {
+                    dirs.push(name.trim_end_matches(".parquet"));
+                } else {
+                    files.push(name);
+                }
+            }
+            if self.emit_seconds_variant {
+                files.push("logs-seconds.parquet");
+            }
+        }
+        for (format, name) in [
+            (Format::Csv, "logs.csv"),
+            (Format::Ndjson, "logs.ndjson"),
+            (Format::Orc, "logs.orc"),
+            (Format::Clf, self.clf_file.as_str()),
+            (Format::Ipc, "logs.arrow"),
+        ] {
+            if self.format.includes(format) {
+                files.push(name);
+            }
+        }
+        if self.manifest {
+            files.push("manifest.json");
+        }
+
+        for name in files {
+            let path = self.path(name);
+            ensure!(
+                !path.is_dir(),
+                "cannot write {}, which is a directory",
+                path.display()
+            );
+        }
+        for name in dirs {
+            let path = self.path(name);
+            ensure!(
+                !path.exists() || path.is_dir(),
+                "cannot write part files to {}, which is not a directory",
+                path.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Creates the sink for the output file `name`
+    fn create(&self, name: &str) -> Result<Box<dyn Write + Send>> {
+        if self.to_stdout() {
+            // ArrowWriter requires a Send sink, which rules out StdoutLock
+            return Ok(Box::new(BufWriter::new(io::stdout())));
+        }
+        let path = self.path(name);
+        let file =
+            File::create(&path).with_context(|| format!("failed to create {}", path.display()))?;
+        Ok(Box::new(BufWriter::new(file)))
+    }
+
+    /// Writes `batches` as the parquet output file `name`, closing a row
+    /// group every --flush-every rows if set
+    fn write_parquet(
+        &self,
+        name: &str,
+        schema: SchemaRef,
+        batches: impl IntoIterator<Item = RecordBatch>,
+        props: WriterProperties,
+    ) -> Result<()> {
+        let sink = self.create(name)?;
+        match self.flush_every {
+            Some(rows) => write_parquet_flushing(sink, schema, batches, props, rows),
+            None => write_parquet(sink, schema, batches, props),
+        }
+        .with_context(|| format!("failed to write {}", self.path(name).display()))
+    }
+
+    /// Wraps `batches` to report the progress of writing the output file
+    /// `name` if `--progress` is set
+    fn progress(
+        &self,
+        name: &str,
+        batches: Box<dyn Iterator<Item = RecordBatch>>,
+    ) -> Box<dyn Iterator<Item = RecordBatch>> {
+        if !self.progress {
+            return batches;
+        }
+        let name = name.to_string();
+        // A window or --limit-total-rows ends the generation after a number
+        // of rows rather than batches, so progress is counted in rows
+        let row_limit = self.row_limit();
+        let total = row_limit.unwrap_or(self.num_batches * self.repeat_batches);
+        let (mut num_batches, mut rows) = (0, 0);
+
+        if io::stderr().is_terminal() {
+            let template = match row_limit {
+                Some(_) => {
+                    "{prefix} [{elapsed_precise}] {wide_bar} {pos}/{len} rows, {msg} batches"
+                }
+                None => "{prefix} [{elapsed_precise}] {wide_bar} {pos}/{len} batches, {msg} rows",
+            };
+            let style = ProgressStyle::with_template(template).expect("valid template");
+            let bar = ProgressBar::new(total as u64)
+                .with_style(style)
+                .with_prefix(name)
+                .with_finish(ProgressFinish::AndLeave);
+            return Box::new(batches.inspect(move |batch| {
+                num_batches += 1;
+                rows += batch.num_rows();
+                let (pos, msg) = match row_limit {
+                    Some(_) => (rows, num_batches),
+                    None => (num_batches, rows),
+                };
+                bar.set_position(pos as u64);
+                bar.set_message(msg.to_string());
+            }));
+        }
+
+        // Control codes would garble logs, so report every tenth instead
+        let step = (total / 10).max(1);
+        let mut reported = 0;
+        Box::new(batches.inspect(move |batch| {
+            num_batches += 1;
+            rows += batch.num_rows();
+            let done = match row_limit {
+                Some(_) => rows,
+                None => num_batches,
+            };
+            if done / step > reported || done == total {
+                reported = done / step;
+                match row_limit {
+                    Some(_) => {
+                        eprintln!("{}: {}/{} rows, {} batches", name, rows, total, num_batches)
+                    }
+                    None => eprintln!("{}: {}/{} batches, {} rows", name, num_batches, total, rows),
+                }
+            }
+        }))
+    }
+
+    /// Wraps `batches` to log each batch generated for the output file
+    /// `name` if `--verbose` is set
+    fn logged(
+        &self,
+        name: &str,
+        batches: Box<dyn Iterator<Item = RecordBatch>>,
+    ) -> Box<dyn Iterator<Item = RecordBatch>> {
+        if !log::log_enabled!(log::Level::Debug) {
+            return batches;
+        }
+        let name = name.to_string();
+        let start = Instant::now();
+        let mut num_batches = 0;
+        Box::new(batches.inspect(move |batch| {
+            num_batches += 1;
+            log::debug!(
+                "file={} batch={} rows={} elapsed_ms={}",
+                name,
+                num_batches,
+                batch.num_rows(),
+                start.elapsed().as_millis()
+            );
+        }))
+    }
+
+    /// Prints `message` unless `--quiet` is set, to stderr when writing to
+    /// stdout
+    fn report(&self, message: impl std::fmt::Display) {
+        if self.quiet {
+            return;
+        }
+        if self.to_stdout() {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+
+    /// Reports that the output file `name` has been written
+    fn written(&self, name: &str) {
+        if self.to_stdout() {
+            return;
+        }
+        let path = self.path(name);
+        log_file_written(&path);
+        self.report(format!("Write {}", path.display()));
+    }
+}
+
+/// The parquet files written with --format parquet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Variant {
+    /// logs-no-stats.parquet, without statistics
+    NoStats,
+    /// logs-chunk-stats.parquet, with column chunk statistics
+    ChunkStats,
+    /// logs-page-stats.parquet, with page statistics
+    PageStats,
+    /// logs-bloom.parquet, with chunk statistics and bloom filters
+    Bloom,
+}
+
+impl Variant {
+    fn file_name(self) -> &'static str {
+        match self {
+            Self::NoStats => "logs-no-stats.parquet",
+            Self::ChunkStats => "logs-chunk-stats.parquet",
+            Self::PageStats => "logs-page-stats.parquet",
+            Self::Bloom => "logs-bloom.parquet",
+        }
+    }
+}
+
+/// How --layout splits each parquet file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Layout {
+    /// One file with every row
+    Single,
+    /// One file per host, as --partition-by host
+    PerHost,
+    /// One file per service, as --partition-by service
+    PerService,
+}
+
+impl Layout {
+    fn partition_by(self) -> Option<PartitionBy> {
+        match self {
+            Self::Single => None,
+            Self::PerHost => Some(PartitionBy::Host),
+            Self::PerService => Some(PartitionBy::Service),
+        }
+    }
+}
+
+/// How --dry-run and --rows-summary print their report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Report {
+    Text,
+    Json,
+}
+
+impl Format {
+    fn includes(self, other: Format) -> bool {
+        self == Format::All || self == other
     }
 }
 
-fn random_string(rng: &mut StdRng, len_range: Range<usize>) -> String {
-    let len = rng.gen_range(len_range);
-    (0..len)
-        .map(|_| rng.gen_range(b'a'..b'z') as char)
-        .collect::<String>()
+fn parse_positive(s: &str) -> Result<usize, String> {
+    let value: usize = s.parse().map_err(|e| format!("{}", e))?;
+    if value == 0 {
+        return Err("must be at least 1".to_string());
+    }
+    Ok(value)
 }
 
-fn generate_sorted_strings(rng: &mut StdRng, count: usize, str_len: Range<usize>) -> Vec<String> {
-    let mut strings: Vec<_> = (0..count)
-        .map(|_| random_string(rng, str_len.clone()))
+/// Parses an inclusive MIN..MAX range of positive counts
+fn parse_count_range(s: &str) -> Result<RangeInclusive<usize>, String> {
+    let range = parse_range(s)?;
+    if *range.start() == 0 {
+        return Err("MIN must be at least 1".to_string());
+    }
+    Ok(range)
+}
+
+/// Parses `MIN..MAX` as the inclusive range of counts from MIN to MAX
+fn parse_range(s: &str) -> Result<RangeInclusive<usize>, String> {
+    let (min, max) = s
+        .split_once("..")
+        .ok_or_else(|| format!("expected MIN..MAX, got \"{}\"", s))?;
+    let parse = |s: &str| s.parse::<usize>().map_err(|e| format!("{}", e));
+    let (min, max) = (parse(min)?, parse(max)?);
+    if min > max {
+        return Err(format!("MIN {} is greater than MAX {}", min, max));
+    }
+    Ok(min..=max)
+}
+
+fn parse_probability(s: &str) -> Result<f64, String> {
+    let value: f64 = s.parse().map_err(|e| format!("{}", e))?;
+    if !(0.0..=1.0).contains(&value) {
+        return Err("must be in [0.0, 1.0]".to_string());
+    }
+    Ok(value)
+}
+
+fn parse_null_rate(s: &str) -> Result<(String, f64), String> {
+    let (column, rate) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected column:rate, got \"{}\"", s))?;
+    Ok((column.to_string(), parse_probability(rate)?))
+}
+
+fn parse_service(s: &str) -> Result<String, String> {
+    if s.is_empty() {
+        return Err("service names must not be empty".to_string());
+    }
+    Ok(s.to_string())
+}
+
+fn parse_archive(s: &str) -> Result<String, String> {
+    if ![".zip", ".tar", ".tar.gz", ".tgz"]
+        .iter()
+        .any(|extension| s.ends_with(extension))
+    {
+        return Err("expected a .zip, .tar, .tar.gz or .tgz file".to_string());
+    }
+    Ok(s.to_string())
+}
+
+fn parse_host_format(s: &str) -> Result<String, String> {
+    // Hosts would all have the same name otherwise
+    if !s.contains("{n}") && !s.contains("{hex}") {
+        return Err("expected a {n} or {hex} placeholder".to_string());
+    }
+    Ok(s.to_string())
+}
+
+fn parse_container_template(s: &str) -> Result<String, String> {
+    // The containers of a pod would have the same name otherwise
+    if !s.contains("{idx}") {
+        return Err("expected an {idx} placeholder".to_string());
+    }
+    Ok(s.to_string())
+}
+
+fn parse_start_time(s: &str) -> Result<i64, String> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|time| time.timestamp_micros())
+        .map_err(|e| format!("invalid RFC3339 timestamp: {}", e))
+}
+
+fn parse_size(s: &str) -> Result<usize, String> {
+    let upper = s.to_ascii_uppercase();
+    let (digits, multiplier) = [("GB", 1 << 30), ("MB", 1 << 20), ("KB", 1 << 10), ("B", 1)]
+        .into_iter()
+        .find_map(|(suffix, multiplier)| Some((upper.strip_suffix(suffix)?, multiplier)))
+        .unwrap_or((upper.as_str(), 1));
+    let value: usize = digits
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid size \"{}\", expected e.g. 128MB", s))?;
+    match value.checked_mul(multiplier) {
+        Some(size) if size > 0 => Ok(size),
+        Some(_) => Err("must be at least 1 byte".to_string()),
+        None => Err(format!("size \"{}\" is too large", s)),
+    }
+}
+
+fn parse_column_encoding(s: &str) -> Result<(String, Encoding), String> {
+    let (column, encoding) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected column:ENCODING, got \"{}\"", s))?;
+    let encoding = encoding
+        .to_ascii_uppercase()
+        .parse()
+        .map_err(|_| format!("unknown encoding \"{}\"", encoding))?;
+    Ok((column.to_string(), encoding))
+}
+
+#[cfg(feature = "encryption")]
+fn parse_encryption_key(s: &str) -> Result<EncryptionKey, String> {
+    // The key itself is left out of errors
+    if s.len() != 32 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!(
+            "expected 32 hex digits, got {} characters",
+            s.chars().count()
+        ));
+    }
+    let key = (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).expect("hex digits"))
         .collect();
+    Ok(EncryptionKey(key))
+}
 
-    strings.sort_unstable();
-    strings
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected key=value, got \"{}\"", s)),
+    }
 }
 
-/// Generates sorted RecordBatch with an access log style schema for a single host
-#[derive(Debug)]
-struct Generator {
-    schema: SchemaRef,
-    rng: StdRng,
-    host_idx: usize,
+fn parse_column_statistics(s: &str) -> Result<(String, EnabledStatistics), String> {
+    let (column, stats) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected column:page|chunk|none, got \"{}\"", s))?;
+    let stats = match stats {
+        "page" => EnabledStatistics::Page,
+        "chunk" => EnabledStatistics::Chunk,
+        "none" => EnabledStatistics::None,
+        _ => {
+            return Err(format!(
+                "unknown statistics \"{}\", expected page, chunk or none",
+                stats
+            ))
+        }
+    };
+    Ok((column.to_string(), stats))
 }
 
-impl Generator {
-    fn new() -> Self {
-        let seed = [
-            1, 0, 0, 0, 23, 0, 3, 0, 200, 1, 0, 0, 210, 30, 8, 0, 1, 0, 21, 0, 6, 0, 0, 0, 0, 0, 5,
-            0, 0, 0, 0, 0,
-        ];
+/// Returns true if parquet can write columns of `data_type` with `encoding`
+///
+/// Dictionary encodings are excluded as they are enabled with --dictionary
+/// rather than set per column
+fn encoding_supported(data_type: &DataType, encoding: Encoding) -> bool {
+    let data_type = match data_type {
+        DataType::Dictionary(_, value) => value.as_ref(),
+        data_type => data_type,
+    };
+    let integer = matches!(
+        data_type,
+        DataType::Int32 | DataType::Int64 | DataType::UInt16 | DataType::Timestamp(_, _)
+    );
+    let string = matches!(data_type, DataType::Utf8);
+    match encoding {
+        Encoding::PLAIN => true,
+        Encoding::DELTA_BINARY_PACKED | Encoding::BYTE_STREAM_SPLIT => integer,
+        Encoding::DELTA_LENGTH_BYTE_ARRAY | Encoding::DELTA_BYTE_ARRAY => string,
+        _ => false,
+    }
+}
+
+fn parse_writer_version(s: &str) -> Result<WriterVersion, String> {
+    match s {
+        "v1" => Ok(WriterVersion::PARQUET_1_0),
+        "v2" => Ok(WriterVersion::PARQUET_2_0),
+        _ => Err(format!(
+            "unknown writer version \"{}\", expected v1 or v2",
+            s
+        )),
+    }
+}
 
-        Self {
-            schema: BatchBuilder::schema(),
-            host_idx: 0,
-            rng: StdRng::from_seed(seed),
+fn parse_timezone(s: &str) -> Result<String, String> {
+    s.parse::<Tz>()
+        .map_err(|_| format!("unknown timezone \"{}\"", s))?;
+    Ok(s.to_string())
+}
+
+fn parse_compression(s: &str) -> Result<Compression, String> {
+    fn parse_level<T: FromStr>(codec: &str, level: &str) -> Result<T, String> {
+        level
+            .parse()
+            .map_err(|_| format!("invalid {} level \"{}\"", codec, level))
+    }
+
+    let (codec, level) = match s.split_once(':') {
+        Some((codec, level)) => (codec, Some(level)),
+        None => (s, None),
+    };
+
+    match (codec, level) {
+        ("none", None) => Ok(Compression::UNCOMPRESSED),
+        ("snappy", None) => Ok(Compression::SNAPPY),
+        ("lz4", None) => Ok(Compression::LZ4),
+        ("lz4_raw", None) => Ok(Compression::LZ4_RAW),
+        ("brotli", None) => Ok(Compression::BROTLI(Default::default())),
+        ("gzip", None) => Ok(Compression::GZIP(Default::default())),
+        ("gzip", Some(level)) => GzipLevel::try_new(parse_level(codec, level)?)
+            .map(Compression::GZIP)
+            .map_err(|e| e.to_string()),
+        ("zstd", None) => Ok(Compression::ZSTD(Default::default())),
+        ("zstd", Some(level)) => ZstdLevel::try_new(parse_level(codec, level)?)
+            .map(Compression::ZSTD)
+            .map_err(|e| e.to_string()),
+        ("none" | "snappy" | "lz4" | "lz4_raw" | "brotli", Some(_)) => {
+            Err(format!("{} does not support a compression level", codec))
         }
+        _ => Err(format!("unknown compression codec \"{}\"", codec)),
     }
 }
 
-impl Iterator for Generator {
-    type Item = RecordBatch;
+fn main() -> Result<()> {
+    let cli = parse_cli()?;
+    // Only this crate logs, the dependencies' logs aren't of interest
+    env_logger::Builder::new()
+        .filter_module(
+            "access_log_gen",
+            if cli.args.verbose {
+                log::LevelFilter::Debug
+            } else {
+                log::LevelFilter::Warn
+            },
+        )
+        .parse_default_env()
+        .init();
+    match &cli.command {
+        Some(Command::Verify { path }) => verify(&cli.args, path),
+        Some(Command::Bench) => bench(&cli.args),
+        None if cli.args.dry_run.is_some() => dry_run(&cli.args),
+        None => match &cli.args.archive {
+            Some(name) => generate_archive(&cli.args, name),
+            None => generate(&cli.args),
+        },
+    }
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let mut builder = BatchBuilder::default();
+/// Parses the command line, using the values of the `--config` file as the
+/// defaults of the options it sets
+///
+/// The values are parsed and validated by the same value parsers as the
+/// command line, so the file accepts exactly what the options do
+fn parse_cli() -> Result<Cli> {
+    let cli = Cli::parse();
+    let Some(path) = &cli.config else {
+        return Ok(cli);
+    };
+    let table: toml::Table = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?
+        .parse()
+        .with_context(|| format!("invalid config file {}", path.display()))?;
 
-        let host = format!(
-            "i-{:016x}.ec2.internal",
-            self.host_idx * 0x7d87f8ed5c5 + 0x1ec3ca3151468928
+    let mut command = Cli::command();
+    for (key, value) in table {
+        let id = key.replace('-', "_");
+        let known = command
+            .get_arguments()
+            .any(|arg| arg.get_id() == id.as_str() && arg.get_long().is_some());
+        ensure!(
+            known && id != "config",
+            "unknown option \"{}\" in {}",
+            key,
+            path.display()
         );
-        self.host_idx += 1;
+        let values = match value {
+            toml::Value::Array(values) => values.iter().map(toml_to_arg).collect(),
+            value => toml_to_arg(&value).map(|value| vec![value]),
+        }
+        .with_context(|| format!("invalid value of \"{}\" in {}", key, path.display()))?;
+        command = command.mut_arg(id, |arg| arg.default_values(values));
+    }
+    Ok(Cli::from_arg_matches(&command.get_matches()).unwrap_or_else(|e| e.exit()))
+}
+
+/// Converts a scalar TOML value to the command line argument it stands for
+fn toml_to_arg(value: &toml::Value) -> Result<String> {
+    match value {
+        toml::Value::String(s) => Ok(s.clone()),
+        toml::Value::Integer(i) => Ok(i.to_string()),
+        toml::Value::Float(f) => Ok(f.to_string()),
+        toml::Value::Boolean(b) => Ok(b.to_string()),
+        toml::Value::Datetime(time) => Ok(time.to_string()),
+        toml::Value::Array(_) | toml::Value::Table(_) => Err(anyhow!(
+            "expected a string, number, boolean or array of them"
+        )),
+    }
+}
+
+/// Logs the size of the file `path` that has been written
+fn log_file_written(path: &Path) {
+    match std::fs::metadata(path) {
+        Ok(metadata) => log::info!("file={} bytes={}", path.display(), metadata.len()),
+        Err(e) => log::warn!("file={} error=\"{}\"", path.display(), e),
+    }
+}
+
+fn generate(args: &Args) -> Result<()> {
+    let start = Instant::now();
+    if args.to_stdout() && args.format == Format::All {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "writing to stdout requires a single --format",
+            )
+            .exit();
+    }
+    if args.to_stdout() && args.variants.as_ref().is_some_and(|v| v.len() != 1) {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "writing to stdout requires a single --variants",
+            )
+            .exit();
+    }
+    if args.nested
+        && (args.format.includes(Format::Csv)
+            || args.format.includes(Format::Clf)
+            || args.format == Format::Orc)
+    {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--nested requires --format parquet, ndjson or ipc",
+            )
+            .exit();
+    }
+    if args.with_query_params && args.format.includes(Format::Csv) {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "CSV has no map type for --with-query-params",
+            )
+            .exit();
+    }
+    if args.with_headers && args.format.includes(Format::Csv) {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "CSV has no list type for --with-headers",
+            )
+            .exit();
+    }
+    if args.schema_drift && (args.target_file_size.is_none() || args.append || args.success_marker)
+    {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--schema-drift requires --target-file-size, without --append or --success-marker",
+            )
+            .exit();
+    }
+    let multi_file = args.multi_file();
+    if args.emit_seconds_variant
+        && (multi_file || args.to_stdout() || !args.format.includes(Format::Parquet))
+    {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--emit-seconds-variant requires --format parquet and an output directory, without --partition-by, --target-file-size or --append",
+            )
+            .exit();
+    }
+    if args.drop_partition_column && args.partition_by().is_none() {
+        Cli::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "--drop-partition-column requires --partition-by or --layout per-host or per-service",
+            )
+            .exit();
+    }
+    if args.success_marker && !multi_file {
+        Cli::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "--success-marker requires --partition-by, --target-file-size or --append",
+            )
+            .exit();
+    }
+    if args.encrypt {
+        if !args.format.includes(Format::Parquet) {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--encrypt requires --format parquet",
+                )
+                .exit();
+        }
+        #[cfg(feature = "encryption")]
+        if args.encryption_key.is_none() {
+            Cli::command()
+                .error(
+                    ErrorKind::MissingRequiredArgument,
+                    "--encrypt requires --encryption-key",
+                )
+                .exit();
+        }
+        // Both read the footers of the files written
+        if args.success_marker || args.append {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--encrypt can't be used with --success-marker or --append",
+                )
+                .exit();
+        }
+        if cfg!(not(feature = "encryption")) {
+            Cli::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    "--encrypt requires building with the encryption feature",
+                )
+                .exit();
+        }
+    }
+    if multi_file && (args.to_stdout() || args.format != Format::Parquet) {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--partition-by, --target-file-size and --append require --format parquet and an output directory",
+            )
+            .exit();
+    }
+    let config = args.config()?;
+    if !args.to_stdout() {
+        std::fs::create_dir_all(&args.out_dir)
+            .with_context(|| format!("failed to create {}", args.out_dir.display()))?;
+        args.check_outputs()?;
+    }
+
+    if let Some(rows) = args.window_rows() {
+        let rows = args.limit_total_rows.map_or(rows, |limit| rows.min(limit));
+        args.report(format!(
+            "Generate {} rows in the --from and --to window",
+            rows
+        ));
+    }
+    let batches = |name: &str| args.progress(name, args.logged(name, args.batches(&config)));
+    let schema = BatchBuilder::schema(&config);
+    // Column options use the flat names even with --nested
+    let flat_schema = BatchBuilder::flat_schema(&config);
+
+    if args.manifest {
+        if args.to_stdout() {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--manifest requires an output directory",
+                )
+                .exit();
+        }
+        let path = args.path("manifest.json");
+        let manifest = manifest(args, &config, &schema)?;
+        std::fs::write(&path, format!("{:#}\n", manifest))
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        args.written("manifest.json");
+    }
+
+    // Lists and maps have several leaves, so columns are found by path
+    let descriptor = ArrowSchemaConverter::new().convert(&schema)?;
+    let sorting_columns: Vec<_> = args
+        .sort_by
+        .iter()
+        .map(|column| {
+            if flat_schema.index_of(column).is_err() {
+                Cli::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!("unknown column \"{}\" in --sort-by", column),
+                    )
+                    .exit();
+            }
+            let path = args.column_path(column);
+            match descriptor
+                .columns()
+                .iter()
+                .position(|leaf| leaf.path() == &path)
+            {
+                Some(idx) => SortingColumn {
+                    column_idx: idx as i32,
+                    descending: false,
+                    nulls_first: false,
+                },
+                None => Cli::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!("can't sort by the list or map column \"{}\"", column),
+                    )
+                    .exit(),
+            }
+        })
+        .collect();
+
+    if args.format.includes(Format::Parquet) {
+        #[cfg(feature = "encryption")]
+        let encryption = args
+            .encrypt
+            .then(|| args.encryption_properties(&schema))
+            .transpose()?;
+        // The writer only leaves out the offset index without page statistics
+        let page_stats = if args.no_page_index {
+            EnabledStatistics::Chunk
+        } else {
+            EnabledStatistics::Page
+        };
+        let props = || {
+            let builder = WriterProperties::builder()
+                .set_compression(args.compression)
+                .set_dictionary_enabled(args.dictionary)
+                .set_max_row_group_size(args.row_group_size)
+                .set_writer_version(args.writer_version)
+                .set_data_page_size_limit(args.data_page_size)
+                .set_data_page_row_count_limit(args.data_page_row_count)
+                .set_sorting_columns(
+                    (!sorting_columns.is_empty()).then(|| sorting_columns.clone()),
+                );
+            let builder =
+                args.column_encoding
+                    .iter()
+                    .fold(builder, |builder, (column, encoding)| {
+                        let column = args.column_path(column);
+                        builder
+                            .set_column_dictionary_enabled(column.clone(), false)
+                            .set_column_encoding(column, *encoding)
+                    });
+            #[cfg(feature = "encryption")]
+            let builder = match &encryption {
+                Some(encryption) => builder.with_file_encryption_properties(encryption.clone()),
+                None => builder,
+            };
+            let builder = builder
+                .set_offset_index_disabled(args.no_page_index)
+                .set_key_value_metadata(Some(args.key_value_metadata()));
+            // Column statistics take precedence over those set per file below
+            args.stats.iter().fold(builder, |builder, (column, stats)| {
+                let stats = match stats {
+                    EnabledStatistics::Page => page_stats,
+                    stats => *stats,
+                };
+                builder.set_column_statistics_enabled(args.column_path(column), stats)
+            })
+        };
 
-        for service in &["frontend", "backend", "database", "cache"] {
-            if self.rng.gen_bool(0.5) {
-                continue;
+        let mut bloom_props = props().set_statistics_enabled(EnabledStatistics::Chunk);
+        for column in args.bloom_columns(&flat_schema) {
+            bloom_props =
+                bloom_props.set_column_bloom_filter_enabled(args.column_path(&column), true);
+        }
+
+        let variants = [
+            (
+                Variant::NoStats,
+                props().set_statistics_enabled(EnabledStatistics::None),
+            ),
+            (
+                Variant::ChunkStats,
+                props().set_statistics_enabled(EnabledStatistics::Chunk),
+            ),
+            (
+                Variant::PageStats,
+                props().set_statistics_enabled(page_stats),
+            ),
+            (Variant::Bloom, bloom_props),
+        ];
+        let write_variant = |variant: Variant,
+                             props: WriterPropertiesBuilder,
+                             batches: mpsc::Receiver<RecordBatch>|
+         -> Result<()> {
+            let name = variant.file_name();
+            if let Some(partition_by) = args.partition_by() {
+                let dir = args.path(name.trim_end_matches(".parquet"));
+                let paths = write_parquet_partitioned(
+                    &dir,
+                    schema.clone(),
+                    batches,
+                    props.build(),
+                    partition_by,
+                    args.drop_partition_column,
+                )?;
+                for path in &paths {
+                    log_file_written(path);
+                }
+                args.report(format!(
+                    "Write {} partitions to {}",
+                    paths.len(),
+                    dir.display()
+                ));
+                if args.success_marker {
+                    write_success_marker(&dir)?;
+                }
+                return Ok(());
+            }
+            if args.target_file_size.is_some() || args.append {
+                let dir = args.path(name.trim_end_matches(".parquet"));
+                let first_part = if args.append {
+                    next_part(&dir, &schema)?
+                } else {
+                    0
+                };
+                std::fs::create_dir_all(&dir)
+                    .with_context(|| format!("failed to create {}", dir.display()))?;
+                let files = write_parquet_rolling(
+                    &dir,
+                    schema.clone(),
+                    batches,
+                    props.build(),
+                    // Without a target size every batch goes in one new part
+                    args.target_file_size.unwrap_or(usize::MAX),
+                    first_part,
+                    args.schema_drift
+                        .then(|| SchemaDrift::new(args.seed.unwrap_or_default())),
+                )?;
+                for (path, rows) in files {
+                    log_file_written(&path);
+                    args.report(format!("Write {} ({} rows)", path.display(), rows));
+                }
+                if args.success_marker {
+                    write_success_marker(&dir)?;
+                }
+                return Ok(());
             }
-            builder.append(&mut self.rng, &host, service);
+            args.write_parquet(name, schema.clone(), batches, props.build())?;
+            args.written(name);
+            Ok(())
+        };
+        // The batches are generated once and sent to a thread writing each
+        // variant, where the bounded channels keep the batches in memory few
+        let selected = args.variants();
+        std::thread::scope(|scope| {
+            let write_variant = &write_variant;
+            let (mut senders, handles): (Vec<_>, Vec<_>) = variants
+                .into_iter()
+                .filter(|(variant, _)| selected.contains(variant))
+                .map(|(variant, props)| {
+                    let (sender, receiver) = mpsc::sync_channel(2);
+                    let handle = scope.spawn(move || write_variant(variant, props, receiver));
+                    (sender, handle)
+                })
+                .unzip();
+            for batch in batches("parquet") {
+                // A writer that failed has dropped its receiver, and its
+                // error is returned once the others are done
+                senders.retain(|sender| sender.send(batch.clone()).is_ok());
+                if senders.is_empty() {
+                    break;
+                }
+            }
+            drop(senders);
+            handles.into_iter().try_for_each(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+        })?;
+
+        if args.emit_seconds_variant {
+            let name = "logs-seconds.parquet";
+            let batches = batches(name).map(time_in_seconds);
+            let schema = time_in_seconds(RecordBatch::new_empty(schema.clone())).schema();
+            args.write_parquet(
+                name,
+                schema,
+                batches,
+                props().set_statistics_enabled(page_stats).build(),
+            )?;
+            args.written(name);
         }
-        Some(builder.finish(Arc::clone(&self.schema)))
     }
+
+    if args.format.includes(Format::Csv) {
+        write_csv(
+            args.create("logs.csv")?,
+            schema.clone(),
+            batches("logs.csv"),
+        )
+        .with_context(|| format!("failed to write {}", args.path("logs.csv").display()))?;
+        args.written("logs.csv");
+    }
+
+    if args.format.includes(Format::Ndjson) {
+        write_ndjson(
+            args.create("logs.ndjson")?,
+            batches("logs.ndjson"),
+            args.json_omit_nulls,
+        )
+        .with_context(|| format!("failed to write {}", args.path("logs.ndjson").display()))?;
+        args.written("logs.ndjson");
+    }
+
+    #[cfg(feature = "orc")]
+    if args.format.includes(Format::Orc) {
+        access_log_gen::write_orc(
+            args.create("logs.orc")?,
+            schema.clone(),
+            batches("logs.orc"),
+        )
+        .with_context(|| format!("failed to write {}", args.path("logs.orc").display()))?;
+        args.written("logs.orc");
+    }
+    #[cfg(not(feature = "orc"))]
+    if args.format == Format::Orc {
+        Cli::command()
+            .error(
+                ErrorKind::InvalidValue,
+                "--format orc requires building with the orc feature",
+            )
+            .exit();
+    }
+
+    if args.format.includes(Format::Clf) {
+        let name = args.clf_file.as_str();
+        let sink = args.create(name)?;
+        write_clf_file(sink, name, batches(name), args.clf_combined)
+            .with_context(|| format!("failed to write {}", args.path(name).display()))?;
+        args.written(name);
+    }
+
+    if args.format.includes(Format::Ipc) {
+        write_ipc(
+            args.create("logs.arrow")?,
+            schema.clone(),
+            batches("logs.arrow"),
+        )
+        .with_context(|| format!("failed to write {}", args.path("logs.arrow").display()))?;
+        args.written("logs.arrow");
+    }
+
+    if let Some(report) = args.rows_summary {
+        rows_summary(args, &config, report);
+    }
+
+    log::info!("step=generate elapsed_ms={}", start.elapsed().as_millis());
+    Ok(())
 }
 
-fn write_parquet(
+/// Prints the [`RowsSummary`] of the batches `args` generate, to stderr when
+/// writing to stdout
+fn rows_summary(args: &Args, config: &GeneratorConfig, report: Report) {
+    // Regenerated like each output file, with the same values
+    let mut summary = RowsSummary::default();
+    for batch in args.batches(config) {
+        summary.add(&batch);
+    }
+
+    let text = match report {
+        Report::Json => serde_json::json!({
+            "num_rows": summary.num_rows,
+            "services": summary.services,
+            "statuses": summary.statuses,
+            "min_time": summary.min_time.map(|time| time.to_rfc3339()),
+            "max_time": summary.max_time.map(|time| time.to_rfc3339()),
+        })
+        .to_string(),
+        Report::Text => {
+            let mut text = format!("Rows: {}\nServices:\n", summary.num_rows);
+            for (service, rows) in &summary.services {
+                text += &format!("  {}: {}\n", service, rows);
+            }
+            text += "Statuses:\n";
+            for (status, rows) in &summary.statuses {
+                text += &format!("  {}: {}\n", status, rows);
+            }
+            let time = |time: Option<DateTime<Utc>>| {
+                time.map_or_else(|| "none".to_string(), |time| time.to_rfc3339())
+            };
+            text += &format!(
+                "Time: {} to {}",
+                time(summary.min_time),
+                time(summary.max_time)
+            );
+            text
+        }
+    };
+    if args.to_stdout() {
+        eprintln!("{}", text);
+    } else {
+        println!("{}", text);
+    }
+}
+
+/// Generates the outputs of `args` in a staging directory in --out-dir, then
+/// writes them to the --archive `name` and removes them
+#[cfg(feature = "archive")]
+fn generate_archive(args: &Args, name: &str) -> Result<()> {
+    if args.to_stdout() {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--archive requires an output directory",
+            )
+            .exit();
+    }
+    let staging = args.path(&format!(".{}.tmp", name));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)
+            .with_context(|| format!("failed to remove {}", staging.display()))?;
+    }
+    let staged = Args {
+        out_dir: staging.clone(),
+        archive: None,
+        ..args.clone()
+    };
+    generate(&staged)?;
+
+    let path = args.path(name);
+    let num_files = access_log_gen::write_archive(&staging, &path)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    std::fs::remove_dir_all(&staging)
+        .with_context(|| format!("failed to remove {}", staging.display()))?;
+    log_file_written(&path);
+    args.report(format!("Write {} ({} files)", path.display(), num_files));
+    Ok(())
+}
+
+#[cfg(not(feature = "archive"))]
+fn generate_archive(_: &Args, _: &str) -> Result<()> {
+    Cli::command()
+        .error(
+            ErrorKind::InvalidValue,
+            "--archive requires building with the archive feature",
+        )
+        .exit()
+}
+
+/// Returns `batch` with `shift_us` microseconds added to `time`, if it has a
+/// `time` column
+fn shift_time(batch: RecordBatch, shift_us: i64) -> RecordBatch {
+    let schema = batch.schema();
+    let Ok(idx) = schema.index_of("time") else {
+        return batch;
+    };
+    if shift_us == 0 {
+        return batch;
+    }
+    let time = batch.column(idx);
+    let shifted: ArrayRef = match time.data_type() {
+        DataType::Timestamp(TimeUnit::Microsecond, tz) => Arc::new(
+            time.as_primitive::<TimestampMicrosecondType>()
+                .unary::<_, TimestampMicrosecondType>(|micros| micros + shift_us)
+                .with_timezone_opt(tz.clone()),
+        ),
+        DataType::Timestamp(TimeUnit::Nanosecond, tz) => Arc::new(
+            time.as_primitive::<TimestampNanosecondType>()
+                .unary::<_, TimestampNanosecondType>(|nanos| nanos + shift_us * 1000)
+                .with_timezone_opt(tz.clone()),
+        ),
+        data_type => unreachable!("unexpected time type {}", data_type),
+    };
+    let mut columns = batch.columns().to_vec();
+    columns[idx] = shifted;
+    RecordBatch::try_new(schema, columns).expect("schema unchanged")
+}
+
+/// Returns `batch` with `time` truncated to whole seconds as a
+/// `TimeUnit::Second` timestamp, if it has a `time` column
+fn time_in_seconds(batch: RecordBatch) -> RecordBatch {
+    let schema = batch.schema();
+    let Ok(idx) = schema.index_of("time") else {
+        return batch;
+    };
+    let DataType::Timestamp(_, tz) = schema.field(idx).data_type() else {
+        unreachable!("time is a timestamp")
+    };
+    let micros = cast(
+        batch.column(idx),
+        &DataType::Timestamp(TimeUnit::Microsecond, tz.clone()),
+    )
+    .expect("timestamps cast to microseconds");
+    // Rounds down, so times before the epoch stay in the same second
+    let seconds = micros
+        .as_primitive::<TimestampMicrosecondType>()
+        .unary::<_, TimestampSecondType>(|micros| micros.div_euclid(1_000_000))
+        .with_timezone_opt(tz.clone());
+
+    let mut fields = schema.fields().to_vec();
+    fields[idx] = Arc::new(
+        schema
+            .field(idx)
+            .clone()
+            .with_data_type(seconds.data_type().clone()),
+    );
+    let mut columns = batch.columns().to_vec();
+    columns[idx] = Arc::new(seconds);
+    RecordBatch::try_new(
+        Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone())),
+        columns,
+    )
+    .expect("columns match fields")
+}
+
+/// Writes `batches` as Common Log Format to `sink`, gzip compressed if `name`
+/// ends in .gz
+fn write_clf_file(
+    sink: impl Write,
     name: &str,
-    schema: SchemaRef,
-    batches: &[RecordBatch],
-    write_props: WriterProperties,
-) {
-    let mut file = File::create(name).unwrap();
-    let mut writer = ArrowWriter::try_new(&mut file, schema, Some(write_props)).unwrap();
-    for batch in batches {
-        writer.write(&batch).unwrap();
-    }
-    writer.close().unwrap();
-}
-
-fn main() {
-    let generator = Generator::new();
-    let schema = generator.schema.clone();
-    let batches = generator.take(40).collect::<Vec<_>>();
-
-    write_parquet(
-        "logs-no-stats.parquet",
-        schema.clone(),
-        &batches,
-        WriterProperties::builder()
-            .set_dictionary_enabled(false)
-            .set_statistics_enabled(EnabledStatistics::None)
-            .build(),
+    batches: impl IntoIterator<Item = RecordBatch>,
+    combined: bool,
+) -> Result<()> {
+    if !name.ends_with(".gz") {
+        return write_clf(sink, batches, combined);
+    }
+    let mut encoder = GzEncoder::new(sink, GzCompression::default());
+    write_clf(&mut encoder, batches, combined)?;
+    // Writes the gzip trailer, which dropping the encoder would do silently
+    encoder.finish()?.flush()?;
+    Ok(())
+}
+
+/// Returns the contents of manifest.json, describing the columns of `schema`
+/// and the parameters of `args` and `config`
+fn manifest(args: &Args, config: &GeneratorConfig, schema: &Schema) -> Result<serde_json::Value> {
+    use serde_json::json;
+
+    fn weights<T: ToString>(weights: &Weighted<T>) -> serde_json::Value {
+        weights
+            .weights()
+            .map(|(value, weight)| (value.to_string(), weight.into()))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    let columns: Vec<_> = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            json!({
+                "name": field.name(),
+                "type": field.data_type().to_string(),
+                "nullable": field.is_nullable(),
+            })
+        })
+        .collect();
+    let start_time = DateTime::from_timestamp_micros(config.start_time)
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_default();
+    // One entry per parameter, as a single json! literal of them all
+    // exceeds the macro recursion limit
+    let parameters: serde_json::Map<_, _> = [
+        ("seed", json!(args.seed)),
+        ("parallel", json!(args.parallel)),
+        ("num_batches", json!(args.num_batches)),
+        ("limit_total_rows", json!(args.limit_total_rows)),
+        ("repeat_batches", json!(args.repeat_batches)),
+        ("repeat_shift_us", json!(args.repeat_shift_us)),
+        (
+            "window",
+            json!(args.window_rows().map(|rows| json!({
+                "from": start_time,
+                "to": args.to.and_then(DateTime::from_timestamp_micros).map(|to| to.to_rfc3339()),
+                "rows": rows,
+            }))),
+        ),
+        ("rows_per_batch", json!(config.rows_per_batch)),
+        ("num_hosts", json!(config.num_hosts)),
+        ("host_format", json!(config.host_format)),
+        ("container_template", json!(config.container_template)),
+        ("image_template", json!(config.image_template)),
+        ("services", json!(config.services)),
+        ("service_probability", json!(config.service_probability)),
+        ("pods_per_service", json!(config.pods_per_service)),
+        ("containers_per_pod", json!(config.containers_per_pod)),
+        ("pod_name_len", json!(config.pod_name_len)),
+        ("sort_pods", json!(config.sort_pods)),
+        ("start_time", json!(start_time)),
+        ("interval_us", json!(config.interval_us)),
+        ("monotonic_time", json!(config.monotonic_time)),
+        (
+            "diurnal",
+            json!(args.diurnal.then(|| json!({
+                "peak_hour": args.peak_hour,
+                "amplitude": args.diurnal_amplitude,
+            }))),
+        ),
+        ("status_weights", json!(weights(&config.status_weights))),
+        ("method_weights", json!(weights(&config.method_weights))),
+        (
+            "protocol_weights",
+            json!(config.protocols.as_ref().map(weights)),
+        ),
+        ("duration_mean_ms", json!(args.duration_mean_ms)),
+        ("duration_p99_ms", json!(args.duration_p99_ms)),
+        ("duration_ms_column", json!(config.duration_ms)),
+        ("upstream_time", json!(config.upstream_time)),
+        ("request_bytes_mean", json!(args.request_bytes_mean)),
+        ("response_bytes_mean", json!(args.response_bytes_mean)),
+        ("correlate_status", json!(config.correlate_status)),
+        (
+            "spikes",
+            json!(config.spikes.map(|spikes| json!({
+                "frequency_per_hour": args.spike_frequency,
+                "duration_s": args.spike_duration_s,
+                "magnitude": args.spike_magnitude,
+                "windows": spike_windows(args, config, &spikes),
+            }))),
+        ),
+        ("null_rates", json!(config.null_rates)),
+        ("referer_null_rate", json!(config.referer_null_rate)),
+        (
+            "referer_internal_ratio",
+            json!(config.referer_internal_ratio),
+        ),
+        ("deterministic", json!(config.deterministic)),
+        ("nested", json!(config.nested)),
+        ("shuffle", json!(config.shuffle)),
+        ("headers", json!(config.headers)),
+        ("query_params", json!(config.query_params)),
+        ("service_tier", json!(config.service_tier)),
+        ("level", json!(config.level)),
+        ("tracing", json!(config.tracing)),
+        ("schema_version", json!(config.schema_version)),
+        ("batch_id", json!(config.batch_id)),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect();
+    Ok(json!({
+        "columns": columns,
+        "column_stats": column_stats(args, config)?,
+        "parameters": parameters,
+        // Part N of each directory has the Nth change, modulo their number
+        "schema_drift": args.schema_drift.then(|| {
+            Drift::CYCLE
+                .iter()
+                .map(|drift| drift.description())
+                .collect::<Vec<_>>()
+        }),
+        "encryption": args.encrypt.then(|| json!({
+            "footer": "encrypted",
+            "columns": if args.encrypt_columns.is_empty() {
+                json!("all")
+            } else {
+                json!(args.encrypt_columns)
+            },
+        })),
+        // Records every option, including those without a parameter above,
+        // but not the encryption key
+        "arguments": redact_key(std::env::args().skip(1)),
+    }))
+}
+
+/// Returns the minimum, maximum and null count of each leaf column of the
+/// batches `args` generate, keyed by its parquet path, e.g. `request.method`
+/// with --nested
+///
+/// The minimum and maximum are numbers for integer columns and strings
+/// otherwise, e.g. ISO 8601 for time, or null if every value is or the column
+/// is a list or map
+fn column_stats(args: &Args, config: &GeneratorConfig) -> Result<serde_json::Value> {
+    fn value(array: Option<&ArrayRef>) -> Result<serde_json::Value> {
+        let Some(array) = array else {
+            return Ok(serde_json::Value::Null);
+        };
+        if array.data_type().is_integer() {
+            let value = cast(array, &DataType::Int64)?;
+            return Ok(value.as_primitive::<Int64Type>().value(0).into());
+        }
+        Ok(array_value_to_string(array, 0)?.into())
+    }
+
+    // The leaves of nested batches are the columns of flat ones
+    let flat_config = GeneratorConfig {
+        nested: false,
+        ..config.clone()
+    };
+    let mut ranges = ColumnRanges::new(&BatchBuilder::flat_schema(&flat_config))?;
+    for batch in args.batches(&flat_config) {
+        ranges.add(&batch)?;
+    }
+    let mut stats = serde_json::Map::new();
+    for (name, min, max, null_count) in ranges.ranges() {
+        stats.insert(
+            args.column_path(name).string(),
+            serde_json::json!({
+                "min": value(min)?,
+                "max": value(max)?,
+                "null_count": null_count,
+            }),
+        );
+    }
+    Ok(stats.into())
+}
+
+/// Replaces the value of --encryption-key in `args` with `<redacted>`
+fn redact_key(args: impl Iterator<Item = String>) -> Vec<String> {
+    let mut redact_next = false;
+    args.map(|arg| {
+        if std::mem::take(&mut redact_next) {
+            "<redacted>".to_string()
+        } else if arg == "--encryption-key" {
+            redact_next = true;
+            arg
+        } else if arg.starts_with("--encryption-key=") {
+            "--encryption-key=<redacted>".to_string()
+        } else {
+            arg
+        }
+    })
+    .collect()
+}
+
+/// Returns the spikes between the first and last `time` of the batches `args`
+/// generate, for tests to check they are detected, or none if --columns
+/// leaves out `time`, as spikes then leave the rows alone
+fn spike_windows(args: &Args, config: &GeneratorConfig, spikes: &Spikes) -> serde_json::Value {
+    let mut range: Option<(i64, i64)> = None;
+    for batch in args.batches(config) {
+        let Some(time) = batch.column_by_name("time") else {
+            break;
+        };
+        let time = cast(time, &DataType::Timestamp(TimeUnit::Microsecond, None))
+            .expect("timestamps cast to microseconds");
+        let time = time.as_primitive::<TimestampMicrosecondType>();
+        if let (Some(first), Some(last)) = (min(time), max(time)) {
+            range = Some(match range {
+                Some((start, end)) => (start.min(first), end.max(last)),
+                None => (first, last),
+            });
+        }
+    }
+    let Some((start, end)) = range else {
+        return serde_json::json!([]);
+    };
+
+    let rfc3339 = |micros| {
+        DateTime::from_timestamp_micros(micros)
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_default()
+    };
+    spikes
+        .windows(start, end)
+        .into_iter()
+        .map(|window| {
+            serde_json::json!({
+                "start": rfc3339(window.start_us),
+                "end": rfc3339(window.end_us),
+                "kind": window.kind.name(),
+            })
+        })
+        .collect()
+}
+
+/// Prints statistics of the batches `args` generate, without writing them
+fn dry_run(args: &Args) -> Result<()> {
+    let config = args.config()?;
+    let schema = BatchBuilder::schema(&config);
+    let mut stats = DatasetStats::new(&schema);
+    for batch in args.batches(&config) {
+        stats.add(&batch)?;
+    }
+
+    if args.dry_run == Some(Report::Json) {
+        let columns: serde_json::Map<_, _> = stats
+            .cardinalities()
+            .map(|(name, cardinality)| (name.to_string(), cardinality.into()))
+            .collect();
+        let report = serde_json::json!({
+            "num_batches": stats.num_batches,
+            "num_rows": stats.num_rows,
+            "memory_size": stats.memory_size,
+            "cardinality": columns,
+        });
+        println!("{}", report);
+        return Ok(());
+    }
+
+    println!("Batches: {}", stats.num_batches);
+    println!("Rows: {}", stats.num_rows);
+    println!("Estimated uncompressed size: {} bytes", stats.memory_size);
+    println!("Cardinality:");
+    for (name, cardinality) in stats.cardinalities() {
+        println!("  {}: {}", name, cardinality);
+    }
+    Ok(())
+}
+
+/// Generates and drops the batches `args` configure, printing how many rows
+/// and bytes of arrays were generated a second
+fn bench(args: &Args) -> Result<()> {
+    let config = args.config()?;
+    let (mut num_batches, mut num_rows, mut memory_size) = (0, 0, 0);
+    let start = Instant::now();
+    for batch in args.batches(&config) {
+        num_batches += 1;
+        num_rows += batch.num_rows();
+        memory_size += batch.get_array_memory_size();
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let mb = memory_size as f64 / (1 << 20) as f64;
+    println!("{:<10} {:>14}", "batches", num_batches);
+    println!("{:<10} {:>14}", "rows", num_rows);
+    println!("{:<10} {:>14.1}", "MB", mb);
+    println!("{:<10} {:>14.3}", "seconds", elapsed);
+    println!("{:<10} {:>14.0}", "rows/s", num_rows as f64 / elapsed);
+    println!("{:<10} {:>14.1}", "MB/s", mb / elapsed);
+    Ok(())
+}
+
+/// Prints the pages of the parquet file at `path` and checks it contains as
+/// many rows as `args` generate
+fn verify(args: &Args, path: &Path) -> Result<()> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+
+    let options = ArrowReaderOptions::new().with_page_index(false);
+    let reader = ParquetRecordBatchReaderBuilder::try_new_with_options(file.try_clone()?, options)
+        .with_context(|| format!("failed to read metadata of {}", path.display()))?;
+
+    // The encodings the writer uses for the --writer-version
+    let props = WriterProperties::builder()
+        .set_writer_version(args.writer_version)
+        .build();
+    let data_page_type = match args.writer_version {
+        WriterVersion::PARQUET_1_0 => PageType::DATA_PAGE,
+        WriterVersion::PARQUET_2_0 => PageType::DATA_PAGE_V2,
+    };
+    let mut dictionary_pages = 0;
+    let chunk_reader = Arc::new(file);
+    for (r_idx, row_group) in reader.metadata().row_groups().iter().enumerate() {
+        for (c_idx, column) in row_group.columns().iter().enumerate() {
+            let page_reader = SerializedPageReader::new(
+                Arc::clone(&chunk_reader),
+                column,
+                row_group.num_rows() as usize,
+                None,
+            )?;
+            for (p_idx, page) in page_reader.enumerate() {
+                let p = page?;
+                println!(
+                    "{}:{}:{} Page({},{},{})",
+                    r_idx,
+                    c_idx,
+                    p_idx,
+                    p.page_type(),
+                    p.encoding(),
+                    p.buffer().len()
+                );
+                let location = || {
+                    format!(
+                        "page {} of {} in row group {} of {}",
+                        p_idx,
+                        column.column_path(),
+                        r_idx,
+                        path.display()
+                    )
+                };
+                if p.page_type() != PageType::DICTIONARY_PAGE {
+                    ensure!(
+                        p.page_type() == data_page_type,
+                        "{} is a {} page, expected {} for --writer-version",
+                        location(),
+                        p.page_type(),
+                        data_page_type
+                    );
+                    continue;
+                }
+                dictionary_pages += 1;
+                ensure!(
+                    p.encoding() == props.dictionary_page_encoding(),
+                    "{} is a dictionary page encoded as {}, expected {}",
+                    location(),
+                    p.encoding(),
+                    props.dictionary_page_encoding()
+                );
+                // The codec of a column chunk compresses its dictionary page
+                // too, levels aren't recorded in the file
+                ensure!(
+                    std::mem::discriminant(&column.compression())
+                        == std::mem::discriminant(&args.compression),
+                    "{} is a dictionary page compressed with {}, expected {}",
+                    location(),
+                    column.compression(),
+                    args.compression
+                );
+            }
+        }
+    }
+    ensure!(
+        !args.dictionary || dictionary_pages > 0,
+        "{} has no dictionary pages but --dictionary is set",
+        path.display()
     );
-    println!("Write logs-no-stats.parquet");
-
-    write_parquet(
-        "logs-chunk-stats.parquet",
-        schema.clone(),
-        &batches,
-        WriterProperties::builder()
-            .set_dictionary_enabled(false)
-            .set_statistics_enabled(EnabledStatistics::Chunk)
-            .build(),
+
+    let key_value_metadata = reader.metadata().file_metadata().key_value_metadata();
+    for expected in args.key_value_metadata() {
+        let found = key_value_metadata.is_some_and(|kvs| kvs.contains(&expected));
+        ensure!(
+            found,
+            "footer of {} has no {}={} metadata",
+            path.display(),
+            expected.key,
+            expected.value.unwrap_or_default()
+        );
+    }
+
+    let expected_rows: usize = args
+        .batches(&args.config()?)
+        .map(|batch| batch.num_rows())
+        .sum();
+    let footer_rows = reader.metadata().file_metadata().num_rows() as usize;
+    ensure!(
+        footer_rows == expected_rows,
+        "footer of {} has {} rows but the generator produced {}",
+        path.display(),
+        footer_rows,
+        expected_rows
     );
-    println!("Write logs-chunk-stats.parquet");
-
-    write_parquet(
-        "logs-page-stats.parquet",
-        schema.clone(),
-        &batches,
-        WriterProperties::builder()
-            .set_dictionary_enabled(false)
-            .set_statistics_enabled(EnabledStatistics::Page)
-            .build(),
+
+    let mut read_rows = 0;
+    for batch in reader.build()? {
+        read_rows += batch?.num_rows();
+    }
+    ensure!(
+        read_rows == expected_rows,
+        "read {} rows from {} but the generator produced {}",
+        read_rows,
+        path.display(),
+        expected_rows
     );
-    println!("Write logs-page-stats.parquet");
-
-    // let file = File::open("logs.parquet").unwrap();
-
-    // let options = ArrowReaderOptions::new().with_page_index(false);
-    // let reader =
-    //     ParquetRecordBatchReaderBuilder::try_new_with_options(file.try_clone().unwrap(), options)
-    //         .unwrap();
-
-    // let chunk_reader = Arc::new(file);
-    // for (r_idx, row_group) in reader.metadata().row_groups().iter().enumerate() {
-    //     for (c_idx, column) in row_group.columns().iter().enumerate() {
-    //         let page_reader = SerializedPageReader::new(
-    //             Arc::clone(&chunk_reader),
-    //             column,
-    //             row_group.num_rows() as usize,
-    //             None,
-    //         )
-    //         .unwrap();
-    //         for (p_idx, page) in page_reader.enumerate() {
-    //             let p = page.unwrap();
-    //             println!(
-    //                 "{}:{}:{} Page({},{},{})",
-    //                 r_idx,
-    //                 c_idx,
-    //                 p_idx,
-    //                 p.page_type(),
-    //                 p.encoding(),
-    //                 p.buffer().len()
-    //             );
-    //         }
-    //     }
-    // }
+    println!("Verified {} rows in {}", expected_rows, path.display());
+    Ok(())
 }
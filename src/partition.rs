@@ -0,0 +1,454 @@
+use crate::SchemaDrift;
+use anyhow::{anyhow, ensure, Context, Result};
+use arrow::array::timezone::Tz;
+use arrow::array::{AsArray, UInt32Array};
+use arrow::compute::{cast, take_record_batch};
+use arrow::datatypes::{
+    DataType, Schema, SchemaRef, TimeUnit, TimestampMicrosecondType, TimestampNanosecondType,
+};
+use arrow::record_batch::RecordBatch;
+use chrono::DateTime;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::file::metadata::{FileMetaData, ParquetMetaData, ParquetMetaDataWriter};
+use parquet::file::properties::WriterProperties;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// How [`write_parquet_partitioned`] splits rows across files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionBy {
+    /// One file per distinct `host`
+    Host,
+    /// One file per distinct `service`, where rows without one are in the
+    /// Hive default partition
+    Service,
+    /// One file per day of `time`, in the timezone of its type or UTC if it
+    /// has none, splitting batches that straddle midnight
+    Date,
+}
+
+impl PartitionBy {
+    /// Returns the name of the partition key in the directory names
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Host => "host",
+            Self::Service => "service",
+            Self::Date => "date",
+        }
+    }
+
+    /// Returns the partition value of each row of `batch`
+    fn values(self, batch: &RecordBatch) -> Result<Vec<String>> {
+        match self {
+            Self::Host | Self::Service => {
+                let key = self.key();
+                let column = batch
+                    .column_by_name(key)
+                    .ok_or_else(|| anyhow!("batch has no {} column", key))?;
+                // Unpacks dictionary encoded values
+                let column = cast(column, &DataType::Utf8)?;
+                Ok(column
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|value| value.unwrap_or(HIVE_DEFAULT_PARTITION).to_string())
+                    .collect())
+            }
+            Self::Date => Ok(times(batch)?
+                .into_iter()
+                .map(|time| time.format("%Y-%m-%d").to_string())
+                .collect()),
+        }
+    }
+}
+
+/// The most files [`write_parquet_partitioned`] keeps open at once, well
+/// within the usual limit of 1024 open files of a process
+const MAX_OPEN_FILES: usize = 256;
+
+/// The partition of rows whose partition column is null, as Hive names it
+const HIVE_DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// Returns the `time` column of `batch` in the timezone of its type, or UTC
+/// if it has none
+pub(crate) fn times(batch: &RecordBatch) -> Result<Vec<DateTime<Tz>>> {
+    let column = batch
+        .column_by_name("time")
+        .ok_or_else(|| anyhow!("batch has no time column"))?;
+    let (micros, tz): (Vec<i64>, _) = match column.data_type() {
+        DataType::Timestamp(TimeUnit::Microsecond, tz) => (
+            column
+                .as_primitive::<TimestampMicrosecondType>()
+                .values()
+                .to_vec(),
+            tz,
+        ),
+        DataType::Timestamp(TimeUnit::Nanosecond, tz) => (
+            column
+                .as_primitive::<TimestampNanosecondType>()
+                .values()
+                .iter()
+                .map(|nanos| nanos.div_euclid(1000))
+                .collect(),
+            tz,
+        ),
+        data_type => return Err(anyhow!("unsupported time type {}", data_type)),
+    };
+    let tz: Tz = tz.as_deref().unwrap_or("+00:00").parse()?;
+    micros
+        .into_iter()
+        .map(|micros| {
+            let time = DateTime::from_timestamp_micros(micros)
+                .ok_or_else(|| anyhow!("time {} out of range", micros))?;
+            Ok(time.with_timezone(&tz))
+        })
+        .collect()
+}
+
+impl FromStr for PartitionBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "host" => Ok(Self::Host),
+            "service" => Ok(Self::Service),
+            "date" => Ok(Self::Date),
+            _ => Err(format!(
+                "unknown partition \"{}\", expected host, service or date",
+                s
+            )),
+        }
+    }
+}
+
+/// Writes `batches` as one parquet file per partition under `dir`, returning
+/// the paths written
+///
+/// Files are laid out following Hive conventions, e.g.
+/// `dir/host=i-0123456789abcdef.ec2.internal/data.parquet`,
+/// `dir/service=frontend/data.parquet` or
+/// `dir/date=2024-01-31/data.parquet`, so engines can discover the
+/// partitions, with values escaped as Hive does. If `drop_column` is set the
+/// column the partition is derived from is omitted from the files, as it is
+/// encoded in the path.
+///
+/// At most 256 files are open at once, closing the one written to longest
+/// ago to open another. A partition whose file was closed continues in
+/// `data-1.parquet`, `data-2.parquet`, etc. Row groups follow the
+/// `max_row_group_size` of `write_props`, however few rows of a partition
+/// each batch has.
+pub fn write_parquet_partitioned(
+    dir: &Path,
+    schema: SchemaRef,
+    batches: impl IntoIterator<Item = RecordBatch>,
+    write_props: WriterProperties,
+    partition_by: PartitionBy,
+    drop_column: bool,
+) -> Result<Vec<PathBuf>> {
+    let projection: Vec<_> = (0..schema.fields().len())
+        .filter(|&i| !(drop_column && schema.field(i).name() == partition_by.key()))
+        .collect();
+    let file_schema = Arc::new(schema.project(&projection)?);
+
+    // The open file of each partition, with the last batch written to it
+    let mut writers: BTreeMap<String, (PathBuf, ArrowWriter<BufWriter<File>>, usize)> =
+        BTreeMap::new();
+    let mut num_files: BTreeMap<String, usize> = BTreeMap::new();
+    let mut paths = Vec::new();
+    for (batch_idx, batch) in batches.into_iter().enumerate() {
+        // Groups the rows of each partition, preserving their order
+        let mut partitions: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+        for (row, value) in partition_by.values(&batch)?.into_iter().enumerate() {
+            partitions.entry(value).or_default().push(row as u32);
+        }
+
+        for (value, rows) in partitions {
+            let part = take_record_batch(&batch, &UInt32Array::from(rows))?.project(&projection)?;
+            if !writers.contains_key(&value) {
+                if writers.len() >= MAX_OPEN_FILES {
+                    let oldest = writers
+                        .iter()
+                        .min_by_key(|(_, (_, _, last))| *last)
+                        .map(|(value, _)| value.clone())
+                        .expect("files are open");
+                    let (path, writer, _) = writers.remove(&oldest).expect("file is open");
+                    paths.push(close_writer(path, writer, 0)?.0);
+                }
+                let file = num_files.entry(value.clone()).or_default();
+                let path = partition_path(dir, partition_by, &value, *file);
+                *file += 1;
+                let writer = create_writer(&path, file_schema.clone(), write_props.clone())?;
+                writers.insert(value.clone(), (path, writer, batch_idx));
+            }
+            let (_, writer, last) = writers.get_mut(&value).expect("file opened above");
+            writer.write(&part)?;
+            *last = batch_idx;
+        }
+    }
+
+    for (_, (path, writer, _)) in writers {
+        paths.push(close_writer(path, writer, 0)?.0);
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Writes `batches` as parquet files `dir/part-0000.parquet`,
+/// `dir/part-0001.parquet`, etc., starting from `first_part`, returning the
+/// path and number of rows of each file
+///
+/// A file is closed once the bytes written to it, plus those buffered for its
+/// current row group, reach `target_size`. Sizes are only checked between
+/// batches, so files overshoot the target by up to a batch.
+///
+/// With `drift`, each part file's schema differs from `schema` as described
+/// by [`SchemaDrift`].
+pub fn write_parquet_rolling(
+    dir: &Path,
+    schema: SchemaRef,
+    batches: impl IntoIterator<Item = RecordBatch>,
+    write_props: WriterProperties,
+    target_size: usize,
+    first_part: usize,
+    drift: Option<SchemaDrift>,
+) -> Result<Vec<(PathBuf, usize)>> {
+    let mut files = Vec::new();
+    let mut current = None;
+    let mut drift_part: Box<dyn FnMut(RecordBatch) -> RecordBatch> = Box::new(|batch| batch);
+    for batch in batches {
+        if current.is_none() {
+            let part = first_part + files.len();
+            let path = dir.join(format!("part-{:04}.parquet", part));
+            let file_schema = match drift {
+                Some(drift) => {
+                    drift_part = Box::new(drift.part(part));
+                    drift.drift(part).schema(&schema)
+                }
+                None => schema.clone(),
+            };
+            let writer = create_writer(&path, file_schema, write_props.clone())?;
+            current = Some((path, writer, 0));
+        }
+        let (_, writer, rows) = current.as_mut().expect("file opened above");
+        let num_rows = batch.num_rows();
+        writer.write(&drift_part(batch))?;
+        *rows += num_rows;
+
+        if writer.bytes_written() + writer.in_progress_size() >= target_size {
+            let (path, writer, rows) = current.take().expect("current file");
+            files.push(close_writer(path, writer, rows)?);
+        }
+    }
+    if let Some((path, writer, rows)) = current {
+        files.push(close_writer(path, writer, rows)?);
+    }
+    Ok(files)
+}
+
+/// Returns the number after the highest of the `part-N.parquet` files in
+/// `dir`, or 0 if there are none, for [`write_parquet_rolling`] to append to
+/// them
+///
+/// Returns an error if a part file's schema doesn't match `schema`
+pub fn next_part(dir: &Path, schema: &Schema) -> Result<usize> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", dir.display())),
+    };
+
+    let mut next = 0;
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("failed to read {}", dir.display()))?
+            .path();
+        let part = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("part-")?.strip_suffix(".parquet"))
+            .and_then(|part| part.parse::<usize>().ok());
+        let Some(part) = part else {
+            continue;
+        };
+
+        let file =
+            File::open(&path).with_context(|| format!("failed to open {}", path.display()))?;
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        ensure!(
+            reader.schema().fields() == schema.fields(),
+            "schema of {} doesn't match the generated schema",
+            path.display()
+        );
+        next = next.max(part + 1);
+    }
+    Ok(next)
+}
+
+/// Writes a `_metadata` file summarizing the row groups of every parquet
+/// file under `dir`, followed by an empty `_SUCCESS` marker, as Spark does
+/// once a job has written a dataset
+///
+/// The files must share a schema, as those of a single
+/// [`write_parquet_partitioned`] or [`write_parquet_rolling`] directory do
+pub fn write_success_marker(dir: &Path) -> Result<()> {
+    let mut paths = Vec::new();
+    find_parquet_files(dir, &mut paths)?;
+    paths.sort();
+
+    let mut first: Option<FileMetaData> = None;
+    let mut row_groups = Vec::new();
+    let mut num_rows = 0;
+    for path in &paths {
+        let file =
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        let metadata = ParquetRecordBatchReaderBuilder::try_new(file)
+            .with_context(|| format!("failed to read {}", path.display()))?
+            .metadata()
+            .as_ref()
+            .clone();
+        let file_path = path
+            .strip_prefix(dir)
+            .expect("found under dir")
+            .to_string_lossy()
+            .into_owned();
+
+        let file_metadata = metadata.file_metadata();
+        num_rows += file_metadata.num_rows();
+        let expected = first.get_or_insert_with(|| file_metadata.clone());
+        ensure!(
+            expected.schema_descr().root_schema() == file_metadata.schema_descr().root_schema(),
+            "schema of {} doesn't match {}",
+            path.display(),
+            paths[0].display()
+        );
+        // Points every column chunk at the file holding it
+        for row_group in metadata.row_groups() {
+            let columns = row_group
+                .columns()
+                .iter()
+                .map(|column| {
+                    column
+                        .clone()
+                        .into_builder()
+                        .set_file_path(file_path.clone())
+                        .build()
+                })
+                .collect::<Result<_, _>>()?;
+            row_groups.push(
+                row_group
+                    .clone()
+                    .into_builder()
+                    .set_column_metadata(columns)
+                    .build()?,
+            );
+        }
+    }
+
+    if let Some(file_metadata) = first {
+        let file_metadata = FileMetaData::new(
+            file_metadata.version(),
+            num_rows,
+            file_metadata.created_by().map(str::to_string),
+            file_metadata.key_value_metadata().cloned(),
+            file_metadata.schema_descr_ptr(),
+            file_metadata.column_orders().cloned(),
+        );
+        let metadata = ParquetMetaData::new(file_metadata, row_groups);
+
+        let path = dir.join("_metadata");
+        let file =
+            File::create(&path).with_context(|| format!("failed to create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        // A footer-only parquet file, starting with the magic number as well
+        writer.write_all(b"PAR1")?;
+        ParquetMetaDataWriter::new(&mut writer, &metadata).finish()?;
+        writer
+            .flush()
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+
+    let path = dir.join("_SUCCESS");
+    File::create(&path).with_context(|| format!("failed to create {}", path.display()))?;
+    Ok(())
+}
+
+/// Appends the parquet files under `dir` to `paths`, skipping hidden files
+/// and those starting with `_` as readers do
+fn find_parquet_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry.with_context(|| format!("failed to read {}", dir.display()))?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') || name.starts_with('_') {
+            continue;
+        }
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            find_parquet_files(&path, paths)?;
+        } else if name.ends_with(".parquet") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn close_writer(
+    path: PathBuf,
+    writer: ArrowWriter<BufWriter<File>>,
+    rows: usize,
+) -> Result<(PathBuf, usize)> {
+    writer
+        .into_inner()?
+        .flush()
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok((path, rows))
+}
+
+/// Returns the path of the `file`th file of the partition `value`
+fn partition_path(dir: &Path, partition_by: PartitionBy, value: &str, file: usize) -> PathBuf {
+    let name = match file {
+        0 => "data.parquet".to_string(),
+        file => format!("data-{}.parquet", file),
+    };
+    dir.join(format!("{}={}", partition_by.key(), escape(value)))
+        .join(name)
+}
+
+/// Returns `value` with the characters Hive escapes in partition directory
+/// names percent-encoded, e.g. `web/1` as `web%2F1`, so a value can't
+/// add or escape directories
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_ascii_control() || "\"#%'*/:=?\\[]^{".contains(c) {
+            escaped.push_str(&format!("%{:02X}", c as u32));
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+fn create_writer(
+    path: &Path,
+    schema: SchemaRef,
+    write_props: WriterProperties,
+) -> Result<ArrowWriter<BufWriter<File>>> {
+    let parent = path.parent().expect("partition paths have a parent");
+    std::fs::create_dir_all(parent)
+        .with_context(|| format!("failed to create {}", parent.display()))?;
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    Ok(ArrowWriter::try_new(
+        BufWriter::new(file),
+        schema,
+        Some(write_props),
+    )?)
+}
@@ -0,0 +1,220 @@
+use crate::random_string;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+/// Request path templates for each service
+///
+/// Templates may contain `{id}`, replaced with a random number, and `{slug}`,
+/// replaced with a random lowercase string
+#[derive(Debug, Clone)]
+pub struct Routes {
+    by_service: HashMap<String, Vec<String>>,
+    /// Templates of services without their own
+    fallback: Vec<String>,
+}
+
+impl Default for Routes {
+    fn default() -> Self {
+        let routes = |templates: &[&str]| templates.iter().map(|t| t.to_string()).collect();
+        let by_service = [
+            (
+                "frontend",
+                routes(&[
+                    "/",
+                    "/index.html",
+                    "/products/{id}",
+                    "/products/{slug}",
+                    "/cart",
+                    "/checkout",
+                    "/static/js/{slug}.js",
+                    "/static/css/{slug}.css",
+                ]),
+            ),
+            (
+                "backend",
+                routes(&[
+                    "/api/v1/users/{id}",
+                    "/api/v1/users/{id}/orders",
+                    "/api/v1/orders/{id}",
+                    "/api/v1/products",
+                    "/api/v1/products/{id}",
+                    "/api/v1/search",
+                ]),
+            ),
+            (
+                "database",
+                routes(&["/query", "/tables/{slug}", "/tables/{slug}/rows/{id}"]),
+            ),
+            ("cache", routes(&["/keys/{slug}", "/keys/{id}", "/stats"])),
+        ]
+        .into_iter()
+        .map(|(service, templates)| (service.to_string(), templates))
+        .collect();
+
+        Self {
+            by_service,
+            fallback: routes(&["/", "/health", "/metrics", "/api/v1/{slug}/{id}"]),
+        }
+    }
+}
+
+impl Routes {
+    /// Parses a routes file
+    ///
+    /// Each non-empty line is a service name followed by whitespace and a path
+    /// template, a service of `*` applies to services without any routes of
+    /// their own. Lines starting with `#` are ignored
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut by_service: HashMap<String, Vec<String>> = HashMap::new();
+        let mut fallback = vec![];
+        for (idx, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (service, template) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| format!("line {}: expected \"<service> <path>\"", idx + 1))?;
+            let template = template.trim().to_string();
+            match service {
+                "*" => fallback.push(template),
+                _ => by_service
+                    .entry(service.to_string())
+                    .or_default()
+                    .push(template),
+            }
+        }
+        if by_service.is_empty() && fallback.is_empty() {
+            return Err("no routes defined".to_string());
+        }
+        if fallback.is_empty() {
+            fallback.push("/".to_string());
+        }
+        Ok(Self {
+            by_service,
+            fallback,
+        })
+    }
+
+    /// Returns a random path for `service`
+    pub(crate) fn sample<R: Rng>(&self, rng: &mut R, service: &str) -> String {
+        let templates = self.by_service.get(service).unwrap_or(&self.fallback);
+        let template = &templates[rng.gen_range(0..templates.len())];
+
+        let mut path = String::with_capacity(template.len());
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find('{') {
+            path.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if let Some(tail) = rest.strip_prefix("{id}") {
+                path.push_str(&rng.gen_range(1..100_000).to_string());
+                rest = tail;
+            } else if let Some(tail) = rest.strip_prefix("{slug}") {
+                path.push_str(&random_string(rng, 4..12));
+                rest = tail;
+            } else {
+                path.push('{');
+                rest = &rest[1..];
+            }
+        }
+        path.push_str(rest);
+        path
+    }
+}
+
+/// Names of query parameters
+const QUERY_KEYS: &[&str] = &["page", "limit", "sort", "q", "filter", "lang"];
+
+/// Returns a random query string without the leading `?`, or `None` for
+/// the `null_rate` fraction of requests without one
+pub(crate) fn random_query<R: Rng>(rng: &mut R, null_rate: f64) -> Option<String> {
+    if rng.gen_bool(null_rate) {
+        return None;
+    }
+    let num_params = rng.gen_range(1..4);
+    let params: Vec<_> = (0..num_params)
+        .map(|_| {
+            let key = QUERY_KEYS[rng.gen_range(0..QUERY_KEYS.len())];
+            format!("{}={}", key, random_string(rng, 1..8))
+        })
+        .collect();
+    Some(params.join("&"))
+}
+
+/// Returns up to 3 random query parameters with distinct names, which may be
+/// none
+pub(crate) fn random_query_params<R: Rng>(rng: &mut R) -> Vec<(&'static str, String)> {
+    let num_params = rng.gen_range(0..4);
+    QUERY_KEYS
+        .choose_multiple(rng, num_params)
+        .map(|&key| (key, random_string(rng, 1..8)))
+        .collect()
+}
+
+/// Returns `count` random request headers, e.g. `Accept: text/html`, or
+/// `None` for the `null_rate` fraction of requests whose headers weren't
+/// recorded
+pub(crate) fn random_headers<R: Rng>(
+    rng: &mut R,
+    count: RangeInclusive<usize>,
+    null_rate: f64,
+) -> Option<Vec<String>> {
+    const HEADERS: &[&str] = &[
+        "Accept: text/html",
+        "Accept: application/json",
+        "Accept: */*",
+        "Accept-Encoding: gzip, deflate, br",
+        "Accept-Language: en-US,en;q=0.9",
+        "Cache-Control: no-cache",
+        "Connection: keep-alive",
+        "DNT: 1",
+        "X-Request-Id: {id}",
+        "X-Forwarded-Proto: https",
+    ];
+
+    if rng.gen_bool(null_rate) {
+        return None;
+    }
+    let num_headers = rng.gen_range(count);
+    let headers = (0..num_headers)
+        .map(|_| {
+            let header = HEADERS[rng.gen_range(0..HEADERS.len())];
+            header.replace("{id}", &random_string(rng, 16..17))
+        })
+        .collect();
+    Some(headers)
+}
+
+/// Returns a random `Referer`, or `None` for a direct hit
+///
+/// With probability `internal_ratio` the referer is another page of `service`
+pub(crate) fn random_referer<R: Rng>(
+    rng: &mut R,
+    routes: &Routes,
+    service: &str,
+    null_rate: f64,
+    internal_ratio: f64,
+) -> Option<String> {
+    const EXTERNAL: &[&str] = &[
+        "https://www.google.com/",
+        "https://www.bing.com/",
+        "https://duckduckgo.com/",
+        "https://news.ycombinator.com/",
+        "https://www.reddit.com/r/{slug}/",
+        "https://t.co/{slug}",
+        "https://www.facebook.com/",
+        "https://github.com/{slug}",
+    ];
+
+    if rng.gen_bool(null_rate) {
+        return None;
+    }
+    if rng.gen_bool(internal_ratio) {
+        let path = routes.sample(rng, service);
+        return Some(format!("https://{}.mydomain.com{}", service, path));
+    }
+    let referer = EXTERNAL[rng.gen_range(0..EXTERNAL.len())];
+    Some(referer.replace("{slug}", &random_string(rng, 4..12)))
+}
@@ -0,0 +1,191 @@
+use arrow::array::{new_null_array, Array, ArrayRef, AsArray};
+use arrow::compute::cast;
+use arrow::datatypes::{
+    DataType, Field, Int32Type, Int64Type, TimeUnit, TimestampNanosecondType, UInt16Type,
+};
+use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+/// A single access log entry, with a field for each column of
+/// [`crate::BatchBuilder::schema`]
+///
+/// Fields of columns that aren't in the batch, e.g. `request_query` without
+/// [`crate::GeneratorConfig::request_query`], are `None`, or empty or 0 if
+/// the column isn't nullable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRow {
+    pub service: Option<String>,
+    pub host: String,
+    pub pod: String,
+    pub container: String,
+    pub image: String,
+    /// `None` if the batch has no `time` column, rather than the epoch
+    pub time: Option<DateTime<Utc>>,
+    pub client_addr: Option<String>,
+    /// In nanoseconds
+    pub request_duration_ns: i64,
+    pub request_duration_ms: Option<i64>,
+    pub upstream_duration_ns: Option<i64>,
+    pub request_user_agent: Option<String>,
+    pub request_method: Option<String>,
+    pub request_host: Option<String>,
+    pub request_path: String,
+    pub request_referer: Option<String>,
+    pub request_query: Option<String>,
+    pub request_protocol: Option<String>,
+    pub request_bytes: Option<i64>,
+    pub response_bytes: Option<i64>,
+    pub response_status: Option<u16>,
+    pub geo_country: Option<String>,
+    pub session_id: Option<String>,
+    pub headers: Option<Vec<String>>,
+    pub query_params: Option<Vec<(String, String)>>,
+    pub service_tier: Option<String>,
+    pub level: String,
+    pub trace_id: Option<String>,
+    pub span_id: Option<String>,
+    pub schema_version: Option<i32>,
+    pub batch_id: Option<i32>,
+}
+
+impl LogRow {
+    /// Returns the rows of `batch`, which must have a subset of the columns
+    /// of [`crate::BatchBuilder::schema`]
+    pub fn from_batch(batch: &RecordBatch) -> Vec<Self> {
+        // Columns are cast so dictionaries are unpacked and either time unit
+        // is read as nanoseconds, missing columns are read as nulls, and
+        // e.g. request_method is read from request.method if nested
+        let column = |name: &str, data_type: &DataType| -> ArrayRef {
+            let column = batch.column_by_name(name).or_else(|| {
+                let (parent, child) = name.split_once('_')?;
+                batch
+                    .column_by_name(parent)?
+                    .as_struct_opt()?
+                    .column_by_name(child)
+            });
+            match column {
+                Some(column) => cast(column, data_type).expect("column casts to its row type"),
+                None => new_null_array(data_type, batch.num_rows()),
+            }
+        };
+        let utf8 = |name: &str| column(name, &DataType::Utf8);
+
+        let service = utf8("service");
+        let host = utf8("host");
+        let pod = utf8("pod");
+        let container = utf8("container");
+        let image = utf8("image");
+        let time = column("time", &DataType::Timestamp(TimeUnit::Nanosecond, None));
+        let client_addr = utf8("client_addr");
+        let request_duration_ns = column("request_duration_ns", &DataType::Int64);
+        let request_duration_ms = column("request_duration_ms", &DataType::Int64);
+        let upstream_duration_ns = column("upstream_duration_ns", &DataType::Int64);
+        let request_user_agent = utf8("request_user_agent");
+        let request_method = utf8("request_method");
+        let request_host = utf8("request_host");
+        let request_path = utf8("request_path");
+        let request_referer = utf8("request_referer");
+        let request_query = utf8("request_query");
+        let request_protocol = utf8("request_protocol");
+        let request_bytes = column("request_bytes", &DataType::Int64);
+        let response_bytes = column("response_bytes", &DataType::Int64);
+        let response_status = column("response_status", &DataType::UInt16);
+        let geo_country = utf8("geo_country");
+        let session_id = utf8("session_id");
+        let headers = column("headers", &DataType::new_list(DataType::Utf8, true));
+        let service_tier = utf8("service_tier");
+        let level = utf8("level");
+        let trace_id = utf8("trace_id");
+        let span_id = utf8("span_id");
+        let schema_version = column("schema_version", &DataType::Int32);
+        let batch_id = column("_batch_id", &DataType::Int32);
+        let query_params = column(
+            "query_params",
+            &DataType::Map(
+                Arc::new(Field::new_struct(
+                    "entries",
+                    vec![
+                        Field::new("keys", DataType::Utf8, false),
+                        Field::new("values", DataType::Utf8, true),
+                    ],
+                    false,
+                )),
+                false,
+            ),
+        );
+
+        let string = |column: &ArrayRef, row: usize| -> Option<String> {
+            let column = column.as_string::<i32>();
+            column.is_valid(row).then(|| column.value(row).to_string())
+        };
+        let int32 = |column: &ArrayRef, row: usize| -> Option<i32> {
+            let column = column.as_primitive::<Int32Type>();
+            column.is_valid(row).then(|| column.value(row))
+        };
+        let int64 = |column: &ArrayRef, row: usize| -> Option<i64> {
+            let column = column.as_primitive::<Int64Type>();
+            column.is_valid(row).then(|| column.value(row))
+        };
+        let value = |column: &ArrayRef, row: usize| string(column, row).unwrap_or_default();
+
+        (0..batch.num_rows())
+            .map(|row| Self {
+                service: string(&service, row),
+                host: value(&host, row),
+                pod: value(&pod, row),
+                container: value(&container, row),
+                image: value(&image, row),
+                time: time.is_valid(row).then(|| {
+                    DateTime::from_timestamp_nanos(
+                        time.as_primitive::<TimestampNanosecondType>().value(row),
+                    )
+                }),
+                client_addr: string(&client_addr, row),
+                request_duration_ns: request_duration_ns.as_primitive::<Int64Type>().value(row),
+                request_duration_ms: int64(&request_duration_ms, row),
+                upstream_duration_ns: int64(&upstream_duration_ns, row),
+                request_user_agent: string(&request_user_agent, row),
+                request_method: string(&request_method, row),
+                request_host: string(&request_host, row),
+                request_path: value(&request_path, row),
+                request_referer: string(&request_referer, row),
+                request_query: string(&request_query, row),
+                request_protocol: string(&request_protocol, row),
+                request_bytes: int64(&request_bytes, row),
+                response_bytes: int64(&response_bytes, row),
+                response_status: {
+                    let column = response_status.as_primitive::<UInt16Type>();
+                    column.is_valid(row).then(|| column.value(row))
+                },
+                geo_country: string(&geo_country, row),
+                session_id: string(&session_id, row),
+                headers: {
+                    let headers = headers.as_list::<i32>();
+                    headers.is_valid(row).then(|| {
+                        let values = headers.value(row);
+                        (0..values.len())
+                            .filter_map(|i| string(&values, i))
+                            .collect()
+                    })
+                },
+                query_params: {
+                    let query_params = query_params.as_map();
+                    query_params.is_valid(row).then(|| {
+                        let entries = query_params.value(row);
+                        let (keys, values) = (entries.column(0), entries.column(1));
+                        (0..entries.len())
+                            .map(|i| (value(keys, i), value(values, i)))
+                            .collect()
+                    })
+                },
+                service_tier: string(&service_tier, row),
+                level: value(&level, row),
+                trace_id: string(&trace_id, row),
+                span_id: string(&span_id, row),
+                schema_version: int32(&schema_version, row),
+                batch_id: int32(&batch_id, row),
+            })
+            .collect()
+    }
+}
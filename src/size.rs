@@ -0,0 +1,46 @@
+use rand::Rng;
+use rand_distr::{Distribution, LogNormal};
+
+/// A log-normal distribution of body sizes in bytes, so most bodies are
+/// small with a heavy tail of large ones
+#[derive(Debug, Clone)]
+pub struct Sizes {
+    distribution: LogNormal<f64>,
+}
+
+impl Sizes {
+    /// Creates a new [`Sizes`] with the given mean in bytes, where `sigma`
+    /// is the standard deviation of the logarithm of the size, the larger
+    /// the heavier the tail
+    ///
+    /// Returns an error unless `mean` and `sigma` are positive
+    pub fn new(mean: f64, sigma: f64) -> Result<Self, String> {
+        if !(mean > 0. && mean.is_finite()) {
+            return Err(format!("expected a positive mean, got {}", mean));
+        }
+        if !(sigma > 0. && sigma.is_finite()) {
+            return Err(format!("expected a positive sigma, got {}", sigma));
+        }
+        // Solves mean = exp(mu + sigma^2 / 2)
+        let mu = mean.ln() - sigma * sigma / 2.;
+        let distribution = LogNormal::new(mu, sigma).map_err(|e| e.to_string())?;
+        Ok(Self { distribution })
+    }
+
+    /// Creates a distribution of request bodies, which rarely stray far
+    /// from `mean`
+    pub fn requests(mean: f64) -> Result<Self, String> {
+        Self::new(mean, 1.)
+    }
+
+    /// Creates a distribution of response bodies, with a tail of downloads
+    /// many times larger than `mean`
+    pub fn responses(mean: f64) -> Result<Self, String> {
+        Self::new(mean, 1.5)
+    }
+
+    /// Returns a random non-negative size in bytes
+    pub(crate) fn sample<R: Rng>(&self, rng: &mut R) -> i64 {
+        self.distribution.sample(rng).min(i64::MAX as f64) as i64
+    }
+}
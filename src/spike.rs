@@ -0,0 +1,237 @@
+use arrow::array::{Array, ArrayRef, AsArray, Int64Array, StringArray, UInt16Array};
+use arrow::compute::cast;
+use arrow::datatypes::{
+    DataType, Int64Type, TimeUnit, TimestampMicrosecondType, TimestampNanosecondType, UInt16Type,
+};
+use arrow::record_batch::RecordBatch;
+use std::sync::Arc;
+
+/// Microseconds in an hour
+const HOUR_US: f64 = 3600e6;
+
+/// What goes wrong during a [`SpikeWindow`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpikeKind {
+    /// Most responses are 503s
+    Errors,
+    /// Requests take many times longer
+    Latency,
+}
+
+impl SpikeKind {
+    /// Returns the lowercase name of the kind
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Errors => "errors",
+            Self::Latency => "latency",
+        }
+    }
+}
+
+/// A window of `[start_us, end_us)` microseconds since the epoch in which
+/// entries are affected by a spike
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpikeWindow {
+    pub start_us: i64,
+    pub end_us: i64,
+    pub kind: SpikeKind,
+}
+
+/// Short windows of errors or slow requests, for testing anomaly detection
+///
+/// Time is divided into consecutive windows of the spike duration, each of
+/// which is a spike or not depending only on `seed` and its start, so the
+/// same windows are spikes whatever the order or number of batches
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spikes {
+    seed: u64,
+    probability: f64,
+    window_us: i64,
+    magnitude: f64,
+}
+
+impl Spikes {
+    /// Creates a new [`Spikes`] with `per_hour` spikes an hour on average,
+    /// each lasting `duration_us` microseconds
+    ///
+    /// During error spikes a `1 - 1 / magnitude` fraction of responses are
+    /// 503s, and during latency spikes requests take `magnitude` times as
+    /// long. Returns an error unless `per_hour` is positive, `duration_us` is
+    /// positive and at most an hour divided by `per_hour`, and `magnitude` is
+    /// at least 1
+    pub fn new(seed: u64, per_hour: f64, duration_us: i64, magnitude: f64) -> Result<Self, String> {
+        if !(per_hour > 0. && per_hour.is_finite()) {
+            return Err(format!("expected a positive frequency, got {}", per_hour));
+        }
+        if duration_us <= 0 {
+            return Err(format!(
+                "expected a positive duration, got {}us",
+                duration_us
+            ));
+        }
+        let probability = per_hour * duration_us as f64 / HOUR_US;
+        if probability > 1. {
+            return Err(format!(
+                "{} spikes of {}us don't fit in an hour",
+                per_hour, duration_us
+            ));
+        }
+        if !(magnitude >= 1. && magnitude.is_finite()) {
+            return Err(format!(
+                "expected a magnitude of at least 1, got {}",
+                magnitude
+            ));
+        }
+        Ok(Self {
+            seed,
+            probability,
+            window_us: duration_us,
+            magnitude,
+        })
+    }
+
+    /// Returns the spike at `time_us`, if any
+    pub fn window(&self, time_us: i64) -> Option<SpikeWindow> {
+        let idx = time_us.div_euclid(self.window_us);
+        if self.uniform(idx as u64, 0) >= self.probability {
+            return None;
+        }
+        let kind = if self.uniform(idx as u64, 1) < 0.5 {
+            SpikeKind::Errors
+        } else {
+            SpikeKind::Latency
+        };
+        let start_us = idx * self.window_us;
+        Some(SpikeWindow {
+            start_us,
+            end_us: start_us + self.window_us,
+            kind,
+        })
+    }
+
+    /// Returns the spikes overlapping `[start_us, end_us]` in order
+    pub fn windows(&self, start_us: i64, end_us: i64) -> Vec<SpikeWindow> {
+        let first = start_us.div_euclid(self.window_us);
+        let last = end_us.div_euclid(self.window_us);
+        (first..=last)
+            .filter_map(|idx| self.window(idx * self.window_us))
+            .collect()
+    }
+
+    /// Returns `batch` with the `response_status` and `request_duration_ns`,
+    /// and its `request_duration_ms` and `upstream_duration_ns`, of the
+    /// entries in a spike replaced, where the columns are in `batch`, and the
+    /// `level` of the entries whose status becomes 503 `ERROR`
+    pub(crate) fn apply(&self, batch: RecordBatch) -> RecordBatch {
+        let schema = batch.schema();
+        let Ok(time_idx) = schema.index_of("time") else {
+            return batch;
+        };
+        let status_idx = schema.index_of("response_status").ok();
+        let duration_idx = schema.index_of("request_duration_ns").ok();
+        let duration_ms_idx = schema.index_of("request_duration_ms").ok();
+        let upstream_idx = schema.index_of("upstream_duration_ns").ok();
+        let level_idx = schema.index_of("level").ok();
+
+        let time = batch.column(time_idx);
+        let times: Vec<i64> = match time.data_type() {
+            DataType::Timestamp(TimeUnit::Microsecond, _) => time
+                .as_primitive::<TimestampMicrosecondType>()
+                .values()
+                .to_vec(),
+            DataType::Timestamp(TimeUnit::Nanosecond, _) => time
+                .as_primitive::<TimestampNanosecondType>()
+                .values()
+                .iter()
+                .map(|nanos| nanos.div_euclid(1000))
+                .collect(),
+            data_type => unreachable!("unexpected time type {}", data_type),
+        };
+        let mut statuses = status_idx.map(|idx| {
+            let column = batch.column(idx).as_primitive::<UInt16Type>();
+            (column.values().to_vec(), column.nulls().cloned())
+        });
+        let values = |idx: usize| {
+            batch
+                .column(idx)
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec()
+        };
+        let mut durations = duration_idx.map(values);
+        let mut upstreams = upstream_idx.map(values);
+        let mut errors = Vec::new();
+
+        let error_rate = 1. - 1. / self.magnitude;
+        for (row, &time) in times.iter().enumerate() {
+            match self.window(time).map(|window| window.kind) {
+                // Draws from the time and row rather than an RNG, so the
+                // affected rows don't depend on the order of batches
+                Some(SpikeKind::Errors) if self.uniform(time as u64, row as u64) < error_rate => {
+                    if let Some((statuses, nulls)) = &mut statuses {
+                        statuses[row] = 503;
+                        // Null statuses stay null, and so do their levels
+                        if nulls.as_ref().is_none_or(|nulls| nulls.is_valid(row)) {
+                            errors.push(row);
+                        }
+                    }
+                }
+                Some(SpikeKind::Latency) => {
+                    // The backend is slow, so the upstream time grows with it
+                    for durations in [&mut durations, &mut upstreams].into_iter().flatten() {
+                        durations[row] =
+                            (durations[row] as f64 * self.magnitude).min(i64::MAX as f64) as i64;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut columns = batch.columns().to_vec();
+        if let (Some(idx), Some((statuses, nulls))) = (status_idx, statuses) {
+            // Null statuses stay null
+            columns[idx] = Arc::new(UInt16Array::new(statuses.into(), nulls)) as ArrayRef;
+        }
+        if let (Some(idx), Some(durations)) = (duration_ms_idx, &durations) {
+            let durations = durations.iter().map(|ns| ns / 1_000_000);
+            columns[idx] = Arc::new(Int64Array::from_iter_values(durations));
+        }
+        if let (Some(idx), Some(durations)) = (duration_idx, durations) {
+            columns[idx] = Arc::new(Int64Array::from(durations));
+        }
+        if let (Some(idx), Some(upstreams)) = (upstream_idx, upstreams) {
+            columns[idx] = Arc::new(Int64Array::from(upstreams));
+        }
+        if let Some(idx) = level_idx.filter(|_| !errors.is_empty()) {
+            columns[idx] = error_levels(batch.column(idx), &errors);
+        }
+        RecordBatch::try_new(schema, columns).expect("schema unchanged")
+    }
+
+    /// Returns a number in `[0, 1)` determined by the seed, `a` and `b`
+    fn uniform(&self, a: u64, b: u64) -> f64 {
+        let hash = splitmix64(splitmix64(self.seed ^ splitmix64(a)) ^ b);
+        (hash >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Returns `levels` with the level of each of `rows` replaced by `ERROR`,
+/// keeping their type
+fn error_levels(levels: &ArrayRef, rows: &[usize]) -> ArrayRef {
+    // Unpacks dictionary encoded levels
+    let strings = cast(levels, &DataType::Utf8).expect("levels are strings");
+    let mut values: Vec<_> = strings.as_string::<i32>().iter().collect();
+    for &row in rows {
+        values[row] = Some("ERROR");
+    }
+    let values: ArrayRef = Arc::new(StringArray::from(values));
+    cast(&values, levels.data_type()).expect("levels are strings")
+}
+
+/// The output function of the SplitMix64 generator
+pub(crate) fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,208 @@
+use crate::LogRow;
+use arrow::array::{Array, ArrayRef};
+use arrow::datatypes::Schema;
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use arrow::row::{OwnedRow, RowConverter, SortField};
+use chrono::{DateTime, Utc};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// The number of rows, size and column cardinalities of a dataset, gathered
+/// batch by batch
+#[derive(Debug)]
+pub struct DatasetStats {
+    /// Number of batches added
+    pub num_batches: usize,
+
+    /// Number of rows added
+    pub num_rows: usize,
+
+    /// Sum of the in-memory sizes of the batches added, an estimate of the
+    /// uncompressed size of the dataset
+    pub memory_size: usize,
+
+    /// Name and hashes of the distinct values of each column
+    columns: Vec<(String, HashSet<u64>)>,
+}
+
+impl DatasetStats {
+    /// Creates empty statistics of batches with `schema`
+    pub fn new(schema: &Schema) -> Self {
+        Self {
+            num_batches: 0,
+            num_rows: 0,
+            memory_size: 0,
+            columns: schema
+                .fields()
+                .iter()
+                .map(|field| (field.name().clone(), HashSet::new()))
+                .collect(),
+        }
+    }
+
+    /// Adds the rows of `batch`, which must have the schema these statistics
+    /// were created with
+    pub fn add(&mut self, batch: &RecordBatch) -> Result<(), ArrowError> {
+        self.num_batches += 1;
+        self.num_rows += batch.num_rows();
+        self.memory_size += batch.get_array_memory_size();
+
+        for ((_, distinct), column) in self.columns.iter_mut().zip(batch.columns()) {
+            // The row format gives every value of any type, including nulls,
+            // a byte representation to hash
+            let converter = RowConverter::new(vec![SortField::new(column.data_type().clone())])?;
+            let rows = converter.convert_columns(std::slice::from_ref(column))?;
+            distinct.extend(rows.iter().map(|row| {
+                let mut hasher = DefaultHasher::new();
+                row.as_ref().hash(&mut hasher);
+                hasher.finish()
+            }));
+        }
+        Ok(())
+    }
+
+    /// Returns the name and number of distinct values, counting null as a
+    /// value, of each column
+    pub fn cardinalities(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.columns
+            .iter()
+            .map(|(name, distinct)| (name.as_str(), distinct.len()))
+    }
+}
+
+/// Row counts by service and status and the range of `time` of a dataset,
+/// for checking the generation options had the intended effect
+#[derive(Debug, Default)]
+pub struct RowsSummary {
+    /// Number of rows added
+    pub num_rows: usize,
+
+    /// Number of rows of each `service`, where nulls are counted as `null`
+    pub services: BTreeMap<String, usize>,
+
+    /// Number of rows of each `response_status`, where nulls are counted as
+    /// `null`
+    pub statuses: BTreeMap<String, usize>,
+
+    /// Earliest `time` added, `None` if the batches have no `time` column
+    pub min_time: Option<DateTime<Utc>>,
+
+    /// Latest `time` added, `None` if the batches have no `time` column
+    pub max_time: Option<DateTime<Utc>>,
+}
+
+impl RowsSummary {
+    /// Adds the rows of `batch`, which must have a subset of the columns of
+    /// [`crate::BatchBuilder::schema`]
+    pub fn add(&mut self, batch: &RecordBatch) {
+        for row in LogRow::from_batch(batch) {
+            self.num_rows += 1;
+            let service = row.service.unwrap_or_else(|| "null".to_string());
+            *self.services.entry(service).or_default() += 1;
+            let status = row
+                .response_status
+                .map_or_else(|| "null".to_string(), |status| status.to_string());
+            *self.statuses.entry(status).or_default() += 1;
+            if let Some(row_time) = row.time {
+                self.min_time = Some(self.min_time.map_or(row_time, |time| time.min(row_time)));
+                self.max_time = Some(self.max_time.map_or(row_time, |time| time.max(row_time)));
+            }
+        }
+    }
+}
+
+/// The minimum, maximum and null count of each column of a dataset, gathered
+/// batch by batch, for checking the results of queries against it
+#[derive(Debug)]
+pub struct ColumnRanges {
+    columns: Vec<ColumnRange>,
+}
+
+/// The range of a single column of [`ColumnRanges`]
+#[derive(Debug)]
+struct ColumnRange {
+    name: String,
+    null_count: usize,
+    /// Converts values to rows that compare in the order of the values, or
+    /// `None` for nested columns, which have no range
+    converter: Option<RowConverter>,
+    /// The row and value of the minimum and maximum
+    min: Option<(OwnedRow, ArrayRef)>,
+    max: Option<(OwnedRow, ArrayRef)>,
+}
+
+impl ColumnRanges {
+    /// Creates empty ranges of batches with `schema`
+    pub fn new(schema: &Schema) -> Result<Self, ArrowError> {
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let data_type = field.data_type();
+                let converter = (!data_type.is_nested())
+                    .then(|| RowConverter::new(vec![SortField::new(data_type.clone())]))
+                    .transpose()?;
+                Ok(ColumnRange {
+                    name: field.name().clone(),
+                    null_count: 0,
+                    converter,
+                    min: None,
+                    max: None,
+                })
+            })
+            .collect::<Result<_, ArrowError>>()?;
+        Ok(Self { columns })
+    }
+
+    /// Adds the rows of `batch`, which must have the schema these ranges
+    /// were created with
+    pub fn add(&mut self, batch: &RecordBatch) -> Result<(), ArrowError> {
+        for (range, column) in self.columns.iter_mut().zip(batch.columns()) {
+            range.null_count += column.null_count();
+            let Some(converter) = &range.converter else {
+                continue;
+            };
+            let rows = converter.convert_columns(std::slice::from_ref(column))?;
+            let valid = (0..column.len()).filter(|&idx| column.is_valid(idx));
+            let (mut min, mut max) = (None, None);
+            for idx in valid {
+                let row = rows.row(idx);
+                if min.is_none_or(|(_, min)| row < min) {
+                    min = Some((idx, row));
+                }
+                if max.is_none_or(|(_, max)| row > max) {
+                    max = Some((idx, row));
+                }
+            }
+            if let Some((idx, row)) = min {
+                if range.min.as_ref().is_none_or(|(min, _)| row < min.row()) {
+                    range.min = Some((row.owned(), column.slice(idx, 1)));
+                }
+            }
+            if let Some((idx, row)) = max {
+                if range.max.as_ref().is_none_or(|(max, _)| row > max.row()) {
+                    range.max = Some((row.owned(), column.slice(idx, 1)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the name, minimum, maximum and null count of each column,
+    /// where the minimum and maximum are single value arrays, or `None` if
+    /// every value is null or the column is nested
+    pub fn ranges(
+        &self,
+    ) -> impl Iterator<Item = (&str, Option<&ArrayRef>, Option<&ArrayRef>, usize)> {
+        self.columns.iter().map(|range| {
+            (
+                range.name.as_str(),
+                range.min.as_ref().map(|(_, value)| value),
+                range.max.as_ref().map(|(_, value)| value),
+                range.null_count,
+            )
+        })
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{random_string, Weighted};
+use rand::Rng;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// How the `request_user_agent` column is generated
+#[derive(Debug, Clone)]
+pub enum UserAgents {
+    /// Random lowercase strings with a length in the range, which must not
+    /// be empty
+    Random(RangeInclusive<usize>),
+    /// The built-in templates, picking a category according to its weight
+    Builtin(Weighted<Category>),
+    /// A custom list, sampled uniformly, must not be empty
+    Custom(Vec<String>),
+}
+
+impl UserAgents {
+    pub(crate) fn sample<R: Rng>(&self, rng: &mut R) -> String {
+        match self {
+            Self::Random(len) => random_string(rng, len.clone()),
+            Self::Builtin(weights) => {
+                let templates = weights.sample(rng).templates();
+                let template = templates[rng.gen_range(0..templates.len())];
+                template.replace("{v}", &rng.gen_range(100..131).to_string())
+            }
+            Self::Custom(agents) => agents[rng.gen_range(0..agents.len())].clone(),
+        }
+    }
+}
+
+/// Approximate user agent category ratios of typical web traffic
+pub const DEFAULT_USER_AGENT_WEIGHTS: &str = "desktop:0.55,mobile:0.35,bot:0.07,cli:0.03";
+
+/// A category of the built-in user agent templates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Desktop,
+    Mobile,
+    Bot,
+    Cli,
+}
+
+impl Category {
+    /// Returns the templates of this category, `{v}` is replaced with a
+    /// random major browser version
+    fn templates(self) -> &'static [&'static str] {
+        match self {
+            Self::Desktop => &[
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{v}.0.0.0 Safari/537.36",
+                "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{v}.0.0.0 Safari/537.36",
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:{v}.0) Gecko/20100101 Firefox/{v}.0",
+                "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{v}.0.0.0 Safari/537.36",
+                "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Safari/605.1.15",
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{v}.0.0.0 Safari/537.36 Edg/{v}.0.0.0",
+            ],
+            Self::Mobile => &[
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1",
+                "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{v}.0.0.0 Mobile Safari/537.36",
+                "Mozilla/5.0 (Linux; Android 13; SM-S918B) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{v}.0.0.0 Mobile Safari/537.36",
+                "Mozilla/5.0 (iPad; CPU OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1",
+            ],
+            Self::Bot => &[
+                "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+                "Mozilla/5.0 (compatible; bingbot/2.0; +http://www.bing.com/bingbot.htm)",
+                "Mozilla/5.0 (compatible; YandexBot/3.0; +http://yandex.com/bots)",
+                "Mozilla/5.0 (compatible; DuckDuckBot-Https/1.1; https://duckduckgo.com/duckduckbot)",
+            ],
+            Self::Cli => &[
+                "curl/8.5.0",
+                "curl/7.81.0",
+                "Wget/1.21.4",
+                "python-requests/2.31.0",
+                "Go-http-client/1.1",
+            ],
+        }
+    }
+}
+
+impl FromStr for Category {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "desktop" => Ok(Self::Desktop),
+            "mobile" => Ok(Self::Mobile),
+            "bot" => Ok(Self::Bot),
+            "cli" => Ok(Self::Cli),
+            _ => Err(format!(
+                "unknown user agent category \"{}\", expected desktop, mobile, bot or cli",
+                s
+            )),
+        }
+    }
+}
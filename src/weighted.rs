@@ -0,0 +1,87 @@
+use rand::Rng;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// A set of values sampled according to their relative weights
+///
+/// Sampling draws a single `f64` from the provided RNG and looks it up in the
+/// cumulative weights, so it is reproducible for a given seed
+#[derive(Debug, Clone)]
+pub struct Weighted<T> {
+    values: Vec<T>,
+    cumulative: Vec<f64>,
+}
+
+impl<T> Weighted<T> {
+    /// Creates a new [`Weighted`] from `(value, weight)` pairs
+    ///
+    /// Returns an error if a weight is negative or not finite, or if the
+    /// weights don't sum to a positive value
+    pub fn new(pairs: impl IntoIterator<Item = (T, f64)>) -> Result<Self, String> {
+        let mut values = vec![];
+        let mut cumulative = vec![];
+        let mut total = 0.;
+        for (value, weight) in pairs {
+            if !weight.is_finite() || weight < 0. {
+                return Err(format!("invalid weight {}", weight));
+            }
+            total += weight;
+            values.push(value);
+            cumulative.push(total);
+        }
+        if total <= 0. {
+            return Err("weights must sum to a positive value".to_string());
+        }
+        Ok(Self { values, cumulative })
+    }
+
+    /// Returns the values that can be sampled, including those with zero weight
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Returns each value with its weight
+    pub fn weights(&self) -> impl Iterator<Item = (&T, f64)> + '_ {
+        let previous = std::iter::once(0.).chain(self.cumulative.iter().copied());
+        self.values
+            .iter()
+            .zip(self.cumulative.iter().zip(previous))
+            .map(|(value, (cumulative, previous))| (value, cumulative - previous))
+    }
+
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> &T {
+        let total = self.cumulative[self.cumulative.len() - 1];
+        let x = rng.gen_range(0.0..total);
+        let idx = self.cumulative.partition_point(|c| *c <= x);
+        &self.values[idx.min(self.values.len() - 1)]
+    }
+}
+
+/// Parses a comma-separated list of `value:weight` pairs, e.g. `200:0.9,503:0.1`
+impl<T: FromStr> FromStr for Weighted<T>
+where
+    T::Err: Display,
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pairs = s
+            .split(',')
+            .map(|pair| {
+                let (value, weight) = pair
+                    .split_once(':')
+                    .ok_or_else(|| format!("expected value:weight, got \"{}\"", pair))?;
+                let value = value
+                    .trim()
+                    .parse()
+                    .map_err(|e| format!("invalid value \"{}\": {}", value, e))?;
+                let weight = weight
+                    .trim()
+                    .parse()
+                    .map_err(|e| format!("invalid weight \"{}\": {}", weight, e))?;
+                Ok((value, weight))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Self::new(pairs)
+    }
+}
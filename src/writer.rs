@@ -0,0 +1,283 @@
+use crate::partition::times;
+use anyhow::Result;
+use arrow::array::{Array, ArrayRef, AsArray};
+use arrow::compute::cast;
+use arrow::csv::WriterBuilder as CsvWriterBuilder;
+use arrow::datatypes::{DataType, SchemaRef};
+use arrow::ipc::writer::FileWriter as IpcFileWriter;
+use arrow::json::writer::LineDelimited;
+use arrow::json::WriterBuilder as JsonWriterBuilder;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use std::io::Write;
+
+/// Writes `batches` as a parquet file to `writer`
+///
+/// Batches are written and dropped one at a time, so memory usage is bounded
+/// by the row group buffered by the writer rather than the whole dataset
+pub fn write_parquet<W: Write + Send>(
+    writer: W,
+    schema: SchemaRef,
+    batches: impl IntoIterator<Item = RecordBatch>,
+    write_props: WriterProperties,
+) -> Result<()> {
+    let mut writer = ArrowWriter::try_new(writer, schema, Some(write_props))?;
+    for batch in batches {
+        writer.write(&batch)?;
+    }
+    // Writes the footer before handing back the sink to be flushed
+    writer.into_inner()?.flush()?;
+    Ok(())
+}
+
+/// Writes `batches` as a parquet file to `writer` like [`write_parquet`],
+/// closing a row group after every `flush_every` rows
+///
+/// Batches are sliced at the boundaries, so every row group but the last has
+/// exactly `flush_every` rows however the batches are sized, unless the
+/// writer's max row group size is smaller. The chunk and page statistics and
+/// bloom filters of each row group then cover those rows alone, e.g. with
+/// monotonic timestamps the time range of row group `i` is that of rows
+/// `i * flush_every..(i + 1) * flush_every`
+pub fn write_parquet_flushing<W: Write + Send>(
+    writer: W,
+    schema: SchemaRef,
+    batches: impl IntoIterator<Item = RecordBatch>,
+    write_props: WriterProperties,
+    flush_every: usize,
+) -> Result<()> {
+    assert!(flush_every > 0, "flush_every must be at least 1");
+    let mut writer = ArrowWriter::try_new(writer, schema, Some(write_props))?;
+    let mut buffered = 0;
+    for batch in batches {
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            let len = (flush_every - buffered).min(batch.num_rows() - offset);
+            writer.write(&batch.slice(offset, len))?;
+            offset += len;
+            buffered += len;
+            if buffered == flush_every {
+                writer.flush()?;
+                buffered = 0;
+            }
+        }
+    }
+    writer.into_inner()?.flush()?;
+    Ok(())
+}
+
+/// Writes `batches` as a parquet file to the async `writer`, e.g. a
+/// `tokio::fs::File` or the upload of an object store
+///
+/// Batches are generated on the calling task as they are written, like
+/// [`write_parquet`], and the writer is shut down once the footer is written
+#[cfg(feature = "async")]
+pub async fn write_parquet_async<W: tokio::io::AsyncWrite + Unpin + Send>(
+    writer: W,
+    schema: SchemaRef,
+    batches: impl IntoIterator<Item = RecordBatch>,
+    write_props: WriterProperties,
+) -> Result<()> {
+    use parquet::arrow::AsyncArrowWriter;
+
+    let mut writer = AsyncArrowWriter::try_new(writer, schema, Some(write_props))?;
+    for batch in batches {
+        writer.write(&batch).await?;
+    }
+    writer.close().await?;
+    Ok(())
+}
+
+/// Writes `batches` as CSV to `writer`
+///
+/// The header row uses the field names of `schema`, timestamps are rendered
+/// in RFC3339 and nulls are written as empty fields
+pub fn write_csv<W: Write>(
+    writer: W,
+    schema: SchemaRef,
+    batches: impl IntoIterator<Item = RecordBatch>,
+) -> Result<()> {
+    let mut writer = CsvWriterBuilder::new()
+        .with_header(true)
+        .with_timestamp_format("%Y-%m-%dT%H:%M:%S%.6fZ".to_string())
+        .with_timestamp_tz_format("%Y-%m-%dT%H:%M:%S%.6f%:z".to_string())
+        .build(writer);
+
+    let mut batches = batches.into_iter().peekable();
+    if batches.peek().is_none() {
+        // Still emit the header row
+        writer.write(&RecordBatch::new_empty(schema))?;
+    }
+    for batch in batches {
+        writer.write(&batch)?;
+    }
+    writer.into_inner().flush()?;
+    Ok(())
+}
+
+/// Writes `batches` as newline-delimited JSON to `writer`
+///
+/// Each row becomes one JSON object with timestamps rendered as ISO-8601
+/// strings, if `omit_nulls` is set null values are left out of the object
+/// rather than written as `null`
+pub fn write_ndjson<W: Write>(
+    writer: W,
+    batches: impl IntoIterator<Item = RecordBatch>,
+    omit_nulls: bool,
+) -> Result<()> {
+    let mut writer = JsonWriterBuilder::new()
+        .with_explicit_nulls(!omit_nulls)
+        .build::<_, LineDelimited>(writer);
+    for batch in batches {
+        writer.write(&batch)?;
+    }
+    writer.finish()?;
+    writer.into_inner().flush()?;
+    Ok(())
+}
+
+/// Writes `batches` as Apache Common Log Format lines to `writer`, or
+/// Combined Log Format if `combined` is set
+///
+/// Each row becomes a line such as
+/// `203.0.113.7 - - [31/Jan/2024:13:55:36 +0000] "GET /api/users?page=2 HTTP/1.1" 200 2326`,
+/// followed in Combined Log Format by the quoted referer and user agent.
+/// Times are rendered in the timezone of the `time` column, or UTC if it has
+/// none, and null or missing fields as `-`, except the protocol, which is
+/// `HTTP/1.1` without a `request_protocol`
+pub fn write_clf<W: Write>(
+    mut writer: W,
+    batches: impl IntoIterator<Item = RecordBatch>,
+    combined: bool,
+) -> Result<()> {
+    for batch in batches {
+        let times = times(&batch)?;
+        // Renders every column as strings, unpacking dictionaries
+        let column = |name: &str| -> Result<Option<ArrayRef>> {
+            batch
+                .column_by_name(name)
+                .map(|column| cast(column, &DataType::Utf8))
+                .transpose()
+                .map_err(Into::into)
+        };
+        let value = |column: &Option<ArrayRef>, row: usize| -> Option<String> {
+            let column = column.as_ref()?.as_string::<i32>();
+            column.is_valid(row).then(|| column.value(row).to_string())
+        };
+        let field = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        // Escapes quoted fields as Apache does
+        let quoted = |value: Option<String>| field(value).replace('"', "\\\"");
+
+        let client_addr = column("client_addr")?;
+        let method = column("request_method")?;
+        let path = column("request_path")?;
+        let query = column("request_query")?;
+        let protocol = column("request_protocol")?;
+        let status = column("response_status")?;
+        let bytes = column("response_bytes")?;
+        let referer = column("request_referer")?;
+        let user_agent = column("request_user_agent")?;
+
+        for (row, time) in times.iter().enumerate() {
+            let target = match (value(&path, row), value(&query, row)) {
+                (Some(path), Some(query)) => format!("{}?{}", path, query),
+                (path, _) => field(path),
+            };
+            write!(
+                writer,
+                "{} - - [{}] \"{} {} {}\" {} {}",
+                field(value(&client_addr, row)),
+                time.format("%d/%b/%Y:%H:%M:%S %z"),
+                field(value(&method, row)),
+                quoted(Some(target)),
+                value(&protocol, row).unwrap_or_else(|| "HTTP/1.1".to_string()),
+                field(value(&status, row)),
+                field(value(&bytes, row)),
+            )?;
+            if combined {
+                write!(
+                    writer,
+                    " \"{}\" \"{}\"",
+                    quoted(value(&referer, row)),
+                    quoted(value(&user_agent, row))
+                )?;
+            }
+            writeln!(writer)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes `batches` as an Arrow IPC file to `writer`
+///
+/// The file carries the same schema, and so the same nullability, as the
+/// parquet output
+pub fn write_ipc<W: Write>(
+    writer: W,
+    schema: SchemaRef,
+    batches: impl IntoIterator<Item = RecordBatch>,
+) -> Result<()> {
+    let mut writer = IpcFileWriter::try_new(writer, &schema)?;
+    for batch in batches {
+        writer.write(&batch)?;
+    }
+    // Writes the footer and end-of-stream marker
+    writer.finish()?;
+    writer.into_inner()?.flush()?;
+    Ok(())
+}
+
+/// Writes `batches` as an ORC file to `writer`
+///
+/// ORC has no unsigned or dictionary types, so `UInt16` columns are widened
+/// to `Int32` and dictionary columns are written as their values. The ORC
+/// writer has no timestamp or decimal types either, so `time` is written as
+/// an `Int64` count of its unit and decimal columns as `Int64`. Other types
+/// and the nullability of every column are unchanged, and lists, maps and
+/// structs are an error
+#[cfg(feature = "orc")]
+pub fn write_orc<W: Write>(
+    writer: W,
+    schema: SchemaRef,
+    batches: impl IntoIterator<Item = RecordBatch>,
+) -> Result<()> {
+    use anyhow::bail;
+    use arrow::compute::cast;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    let orc_type = |data_type: &DataType| match data_type {
+        DataType::UInt16 => DataType::Int32,
+        DataType::Dictionary(_, value) => value.as_ref().clone(),
+        DataType::Timestamp(_, _) | DataType::Decimal128(_, _) => DataType::Int64,
+        data_type => data_type.clone(),
+    };
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    for field in schema.fields() {
+        let data_type = orc_type(field.data_type());
+        if data_type.is_nested() {
+            bail!(
+                "ORC files can't have the list, map or struct column {}",
+                field.name()
+            );
+        }
+        fields.push(Field::new(field.name(), data_type, field.is_nullable()));
+    }
+    let orc_schema = Arc::new(Schema::new(fields));
+
+    let mut writer = orc_rust::ArrowWriterBuilder::new(writer, orc_schema.clone()).try_build()?;
+    for batch in batches {
+        let columns = batch
+            .columns()
+            .iter()
+            .zip(orc_schema.fields())
+            .map(|(column, field)| cast(column, field.data_type()))
+            .collect::<Result<Vec<_>, _>>()?;
+        writer.write(&RecordBatch::try_new(orc_schema.clone(), columns)?)?;
+    }
+    // Writes the stripe footers and file tail
+    writer.close()?;
+    Ok(())
+}
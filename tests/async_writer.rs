@@ -0,0 +1,36 @@
+//! Writes a parquet file with the `async` feature's writer and reads it back
+
+#![cfg(feature = "async")]
+
+use access_log_gen::{write_parquet_async, Generator, LogRow};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::file::properties::WriterProperties;
+
+#[tokio::test]
+async fn write_parquet_async_round_trips() {
+    let generator = Generator::with_seed(1);
+    let schema = generator.schema.clone();
+    let batches: Vec<_> = generator.take(2).collect();
+
+    let path = std::env::temp_dir().join(format!(
+        "access-log-gen-async-{}.parquet",
+        std::process::id()
+    ));
+    let file = tokio::fs::File::create(&path).await.unwrap();
+    write_parquet_async(file, schema, batches.clone(), WriterProperties::default())
+        .await
+        .unwrap();
+
+    let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap())
+        .unwrap()
+        .build()
+        .unwrap();
+    let read: Vec<_> = reader.map(|batch| batch.unwrap()).collect();
+    std::fs::remove_file(&path).unwrap();
+
+    let rows = |batches: &[RecordBatch]| -> Vec<LogRow> {
+        batches.iter().flat_map(LogRow::from_batch).collect()
+    };
+    assert_eq!(rows(&read), rows(&batches));
+}
@@ -0,0 +1,75 @@
+//! Checks `--columns` works with the defaults of the options naming columns,
+//! which may name columns it leaves out, and with spikes and the rows summary
+//! when it leaves out `time`
+
+mod common;
+
+use common::{command, generate, TempDir};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::fs::{self, File};
+
+#[test]
+fn default_options() {
+    let dir = TempDir::new("columns");
+    let args = [
+        "--num-batches",
+        "2",
+        "--columns",
+        "time,host,response_status",
+    ];
+    generate(&args, dir.path());
+
+    let file = File::open(dir.join("logs-bloom.parquet")).unwrap();
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+    let names: Vec<_> = reader
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().as_str())
+        .collect();
+    assert_eq!(names, ["host", "time", "response_status"]);
+}
+
+/// Spikes are placed by time, so without it the manifest has none
+#[test]
+fn spikes_without_time() {
+    let dir = TempDir::new("columns-spikes");
+    let args = [
+        "--num-batches",
+        "2",
+        "--rows-per-batch",
+        "1000",
+        "--columns",
+        "host,response_status,client_addr,request_user_agent",
+        "--inject-spikes",
+        "--manifest",
+    ];
+    generate(&args, dir.path());
+
+    let manifest = fs::read_to_string(dir.join("manifest.json")).unwrap();
+    let manifest: serde_json::Value = serde_json::from_str(&manifest).unwrap();
+    let windows = &manifest["parameters"]["spikes"]["windows"];
+    assert_eq!(windows, &serde_json::json!([]), "{}", manifest);
+}
+
+/// The rows summary has no time range without time, rather than the epoch
+#[test]
+fn rows_summary_without_time() {
+    let dir = TempDir::new("columns-summary");
+    let output = command()
+        .args(["--num-batches", "2", "--rows-per-batch", "1000"])
+        .args(["--columns", "host,response_status", "--format", "csv"])
+        .args(["--rows-summary=json", "--out-dir"])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // Follows the report of the files written
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let summary: serde_json::Value =
+        serde_json::from_str(stdout.lines().next_back().unwrap()).unwrap();
+    assert_eq!(summary["num_rows"], 2000);
+    assert!(summary["min_time"].is_null(), "{}", summary);
+    assert!(summary["max_time"].is_null(), "{}", summary);
+}
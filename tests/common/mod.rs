@@ -0,0 +1,65 @@
+//! Runs the binary against temporary output directories for the integration
+//! tests
+
+// Each test crate uses a different part of this module
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A new empty directory under the system temp directory, removed when
+/// dropped, so also when an assertion of the test fails
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates the directory, named after `name` and the test process
+    pub fn new(name: &str) -> Self {
+        let dir =
+            std::env::temp_dir().join(format!("access-log-gen-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Returns a command running the binary
+pub fn command() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_access-log-gen"))
+}
+
+/// Runs the binary with `args` writing to `out_dir`, asserting it succeeds
+pub fn generate(args: &[&str], out_dir: &Path) {
+    let status = command()
+        .args(args)
+        .arg("--out-dir")
+        .arg(out_dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+/// Returns whether `verify` passes for the file `path` written with `args`
+pub fn verify(args: &[&str], path: &Path) -> bool {
+    command()
+        .args(args)
+        .arg("verify")
+        .arg(path)
+        .status()
+        .unwrap()
+        .success()
+}
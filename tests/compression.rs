@@ -0,0 +1,48 @@
+//! Checks files written with each LZ4 codec are marked with it and pass
+//! `verify`, as do files with compressed dictionary and v2 data pages
+
+mod common;
+
+use common::{generate, verify, TempDir};
+use parquet::basic::Compression;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use std::fs::File;
+
+const ARGS: [&str; 4] = ["--num-batches", "2", "--variants", "page-stats"];
+
+fn check_codec(codec: &str, expected: Compression) {
+    check_codec_with(codec, codec, expected, &[]);
+}
+
+/// Checks `codec` as [`check_codec`] with the further options `args`, in a
+/// directory named after `name`
+fn check_codec_with(name: &str, codec: &str, expected: Compression, args: &[&str]) {
+    let dir = TempDir::new(name);
+    let args = [&ARGS[..], &["--compression", codec], args].concat();
+    generate(&args, dir.path());
+    let path = dir.join("logs-page-stats.parquet");
+
+    let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+    for row_group in reader.metadata().row_groups() {
+        for column in row_group.columns() {
+            assert_eq!(column.compression(), expected);
+        }
+    }
+    assert!(verify(&args, &path));
+}
+
+#[test]
+fn lz4_raw() {
+    check_codec("lz4_raw", Compression::LZ4_RAW);
+}
+
+#[test]
+fn lz4() {
+    check_codec("lz4", Compression::LZ4);
+}
+
+#[test]
+fn dictionary_v2_pages() {
+    let args = ["--dictionary", "--writer-version", "v2"];
+    check_codec_with("dictionary-v2", "snappy", Compression::SNAPPY, &args);
+}
@@ -0,0 +1,37 @@
+//! Checks `--flush-every` closes a row group after every N rows whatever the
+//! batch size
+
+mod common;
+
+use common::{generate, TempDir};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use std::fs::File;
+
+#[test]
+fn flush_every() {
+    let dir = TempDir::new("flush-every");
+    let args = [
+        "--num-batches",
+        "3",
+        "--rows-per-batch",
+        "1000",
+        "--variants",
+        "chunk-stats",
+        "--flush-every",
+        "300",
+    ];
+    generate(&args, dir.path());
+
+    let file = File::open(dir.join("logs-chunk-stats.parquet")).unwrap();
+    let reader = SerializedFileReader::new(file).unwrap();
+    let rows: Vec<i64> = reader
+        .metadata()
+        .row_groups()
+        .iter()
+        .map(|row_group| row_group.num_rows())
+        .collect();
+
+    let (last, full) = rows.split_last().unwrap();
+    assert!(full.iter().all(|&n| n == 300), "{:?}", rows);
+    assert_eq!(full.len() as i64 * 300 + last, 3000);
+}
@@ -0,0 +1,48 @@
+//! Checks the `--kv-metadata` entries and the generator version and seed
+//! round-trip through the parquet footer and pass `verify`
+
+mod common;
+
+use common::{generate, verify, TempDir};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use std::collections::HashMap;
+use std::fs::File;
+
+const ARGS: [&str; 8] = [
+    "--num-batches",
+    "2",
+    "--variants",
+    "page-stats",
+    "--seed",
+    "7",
+    "--kv-metadata",
+    "team=storage",
+];
+
+#[test]
+fn kv_metadata_round_trips() {
+    let dir = TempDir::new("kv");
+    generate(&ARGS, dir.path());
+    let path = dir.join("logs-page-stats.parquet");
+
+    let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+    let metadata: HashMap<_, _> = reader
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .unwrap()
+        .iter()
+        .map(|kv| (kv.key.as_str(), kv.value.as_deref()))
+        .collect();
+    assert_eq!(metadata["team"], Some("storage"));
+    assert_eq!(metadata["access_log_gen.seed"], Some("7"));
+    assert_eq!(
+        metadata["access_log_gen.version"],
+        Some(env!("CARGO_PKG_VERSION"))
+    );
+
+    assert!(verify(&ARGS, &path));
+    // Entries missing from the footer fail verification
+    let missing = [&ARGS[..], &["--kv-metadata", "team=compute"]].concat();
+    assert!(!verify(&missing, &path));
+}
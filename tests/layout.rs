@@ -0,0 +1,95 @@
+//! Checks `--layout per-service` writes one file per service, without the
+//! service column given `--drop-partition-column`, and `--layout per-host`
+//! writes more hosts than the process can have files open, escaping the `/`
+//! of host names
+
+mod common;
+
+use common::{generate, TempDir};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::fs::{self, File};
+
+#[test]
+fn per_service() {
+    let dir = TempDir::new("layout");
+    let args = [
+        "--num-batches",
+        "2",
+        "--variants",
+        "chunk-stats",
+        "--layout",
+        "per-service",
+        "--drop-partition-column",
+    ];
+    generate(&args, dir.path());
+
+    let files = dir.join("logs-chunk-stats");
+    let mut partitions: Vec<_> = fs::read_dir(&files)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    partitions.sort();
+    assert!(!partitions.is_empty());
+    for partition in &partitions {
+        assert!(partition.starts_with("service="), "{:?}", partitions);
+        let file = File::open(files.join(partition).join("data.parquet")).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        assert!(reader.schema().field_with_name("service").is_err());
+    }
+}
+
+/// More hosts than files the process may open, where each host's file is
+/// closed to open those of later hosts
+#[cfg(unix)]
+#[test]
+fn per_host_many_hosts() {
+    let dir = TempDir::new("layout-many-hosts");
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg("ulimit -n 512 && exec \"$0\" \"$@\"")
+        .arg(env!("CARGO_BIN_EXE_access-log-gen"))
+        .args(["--num-batches", "600", "--rows-per-batch", "10"])
+        .args([
+            "--variants",
+            "chunk-stats",
+            "--layout",
+            "per-host",
+            "--out-dir",
+        ])
+        .arg(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(
+        fs::read_dir(dir.join("logs-chunk-stats")).unwrap().count(),
+        600
+    );
+}
+
+/// Host names with a `/` stay in a single directory each
+#[test]
+fn per_host_escapes_values() {
+    let dir = TempDir::new("layout-escape");
+    let args = [
+        "--num-batches",
+        "2",
+        "--host-format",
+        "web/{n}",
+        "--variants",
+        "chunk-stats",
+        "--layout",
+        "per-host",
+    ];
+    generate(&args, dir.path());
+
+    let files = dir.join("logs-chunk-stats");
+    let mut partitions: Vec<_> = fs::read_dir(&files)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    partitions.sort();
+    assert_eq!(partitions, ["host=web%2F0", "host=web%2F1"]);
+    for partition in &partitions {
+        assert!(files.join(partition).join("data.parquet").is_file());
+    }
+}
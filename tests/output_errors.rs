@@ -0,0 +1,99 @@
+//! Checks the binary reports unwritable outputs with their path instead of
+//! panicking, and writes nothing when an output collides with a directory or
+//! an option names an unknown column
+
+mod common;
+
+use common::{command, TempDir};
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+fn run(out_dir: &Path, args: &[&str]) -> Output {
+    command()
+        .args(["--num-batches", "1", "--out-dir"])
+        .arg(out_dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[cfg(unix)]
+#[test]
+fn read_only_out_dir() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new("read-only");
+    fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o555)).unwrap();
+    // Permissions don't apply to root, so there is nothing to check
+    let probe = dir.join("probe");
+    if fs::write(&probe, "").is_ok() {
+        fs::remove_file(&probe).unwrap();
+        return;
+    }
+
+    let output = run(dir.path(), &["--format", "csv"]);
+    // Lets the directory be removed
+    fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o755)).unwrap();
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains(&dir.join("logs.csv").display().to_string()),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("os error"), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
+
+#[test]
+fn output_file_is_a_directory() {
+    let dir = TempDir::new("file-is-dir");
+    fs::create_dir(dir.join("logs.csv")).unwrap();
+
+    let output = run(dir.path(), &["--format", "all"]);
+    let written: Vec<_> = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(stderr.contains("which is a directory"), "{}", stderr);
+    // The collision is found before the parquet files are written
+    assert_eq!(written, ["logs.csv"]);
+}
+
+#[test]
+fn output_directory_is_a_file() {
+    let dir = TempDir::new("dir-is-file");
+    fs::write(dir.join("logs-page-stats"), "").unwrap();
+
+    let output = run(
+        dir.path(),
+        &["--variants", "page-stats", "--target-file-size", "1MB"],
+    );
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(stderr.contains("which is not a directory"), "{}", stderr);
+}
+
+#[test]
+fn unknown_column_creates_nothing() {
+    let dir = TempDir::new("unknown-column");
+    let out_dir = dir.join("out");
+
+    let output = run(&out_dir, &["--stats", "no_such_column:page"]);
+
+    // A usage error, as clap reports them
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = stderr(&output);
+    assert!(stderr.contains("no_such_column"), "{}", stderr);
+    assert!(!out_dir.exists());
+}
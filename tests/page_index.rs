@@ -0,0 +1,47 @@
+//! Checks files written with `--no-page-index` have neither a column index
+//! nor an offset index, keep their chunk statistics and pass `verify`
+
+mod common;
+
+use common::{generate, verify, TempDir};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use std::fs::File;
+
+const ARGS: [&str; 4] = ["--num-batches", "2", "--variants", "page-stats"];
+
+/// Writes logs-page-stats.parquet with `args`, returning whether each column
+/// chunk has a column index and an offset index
+fn page_indexes(name: &str, args: &[&str]) -> Vec<(bool, bool)> {
+    let dir = TempDir::new(name);
+    let args = [&ARGS[..], args].concat();
+    generate(&args, dir.path());
+    let path = dir.join("logs-page-stats.parquet");
+
+    let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+    let mut indexes = Vec::new();
+    for row_group in reader.metadata().row_groups() {
+        for column in row_group.columns() {
+            assert!(column.statistics().is_some());
+            indexes.push((
+                column.column_index_offset().is_some(),
+                column.offset_index_offset().is_some(),
+            ));
+        }
+    }
+    assert!(verify(&args, &path));
+    indexes
+}
+
+#[test]
+fn no_page_index() {
+    let indexes = page_indexes("no-page-index", &["--no-page-index"]);
+    assert!(!indexes.is_empty());
+    assert!(indexes.iter().all(|&index| index == (false, false)));
+}
+
+#[test]
+fn page_index() {
+    let indexes = page_indexes("page-index", &[]);
+    assert!(!indexes.is_empty());
+    assert!(indexes.iter().all(|&index| index == (true, true)));
+}
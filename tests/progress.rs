@@ -0,0 +1,52 @@
+//! Checks `--progress` counts rows against `--limit-total-rows` when it ends
+//! the generation before `--num-batches` runs out, and reports one progress
+//! for the parquet variants
+
+mod common;
+
+use common::{command, TempDir};
+
+#[test]
+fn limit_total_rows() {
+    let dir = TempDir::new("progress");
+    let output = command()
+        .args(["--num-batches", "40", "--limit-total-rows", "150"])
+        .args(["--format", "csv", "--progress", "--out-dir"])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // stderr isn't a terminal, so progress is reported as lines
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let last = stderr
+        .lines()
+        .rfind(|line| line.starts_with("logs.csv: "))
+        .unwrap();
+    assert!(last.starts_with("logs.csv: 150/150 rows, "), "{}", stderr);
+}
+
+/// The parquet variants share one generation, so report one progress
+#[test]
+fn parquet_variants() {
+    let dir = TempDir::new("progress-parquet");
+    let output = command()
+        .args(["--num-batches", "20", "--rows-per-batch", "100"])
+        .args(["--format", "parquet", "--progress", "--out-dir"])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.lines().all(|line| line.starts_with("parquet: ")),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.ends_with("parquet: 20/20 batches, 2000 rows\n"),
+        "{}",
+        stderr
+    );
+}
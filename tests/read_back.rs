@@ -0,0 +1,64 @@
+//! Reads back every parquet file the binary writes for a small dataset,
+//! checking each has the generator's schema and the same rows, which are
+//! generated once for all of them even without `--seed`
+
+mod common;
+
+use access_log_gen::{BatchBuilder, GeneratorConfig};
+use arrow::compute::concat_batches;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use common::{generate, TempDir};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::fs::File;
+
+const FILES: [&str; 4] = [
+    "logs-no-stats.parquet",
+    "logs-chunk-stats.parquet",
+    "logs-page-stats.parquet",
+    "logs-bloom.parquet",
+];
+
+#[test]
+fn read_back_variants() {
+    let dir = TempDir::new("read-back");
+    generate(&["--num-batches", "2", "--seed", "1"], dir.path());
+
+    let schema = BatchBuilder::schema(&GeneratorConfig::default());
+    let rows: Vec<usize> = FILES
+        .iter()
+        .map(|name| {
+            let file = File::open(dir.join(name)).unwrap();
+            let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+                .unwrap()
+                .build()
+                .unwrap();
+            assert_eq!(reader.schema().fields(), schema.fields(), "{}", name);
+            reader.map(|batch| batch.unwrap().num_rows()).sum()
+        })
+        .collect();
+
+    assert!(rows[0] > 0);
+    assert!(rows.iter().all(|&n| n == rows[0]), "{:?}", rows);
+}
+
+#[test]
+fn variants_share_rows() {
+    let dir = TempDir::new("read-back-shared");
+    generate(&["--num-batches", "2"], dir.path());
+
+    let rows: Vec<RecordBatch> = FILES
+        .iter()
+        .map(|name| {
+            let file = File::open(dir.join(name)).unwrap();
+            let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+                .unwrap()
+                .build()
+                .unwrap();
+            let schema = reader.schema();
+            let batches: Vec<_> = reader.map(|batch| batch.unwrap()).collect();
+            concat_batches(&schema, &batches).unwrap()
+        })
+        .collect();
+
+    assert!(rows.iter().all(|batch| batch == &rows[0]));
+}
@@ -0,0 +1,88 @@
+//! Guards the output for a fixed seed against accidental changes
+//!
+//! Each test renders a small dataset as CSV and compares a checksum of it to
+//! a golden file in `tests/golden`. If generation changes on purpose, rerun
+//! with `UPDATE_GOLDEN=1` and commit the updated files, which is the only
+//! way they are written.
+
+use access_log_gen::{write_csv, Generator, GeneratorConfig};
+use arrow::record_batch::RecordBatch;
+use std::path::PathBuf;
+
+const SEED: u64 = 42;
+const NUM_BATCHES: usize = 3;
+
+fn config() -> GeneratorConfig {
+    GeneratorConfig {
+        rows_per_batch: Some(1000),
+        ..Default::default()
+    }
+}
+
+/// FNV-1a, unlike the std hashers stable across Rust versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn check_golden(name: &str, batches: impl IntoIterator<Item = RecordBatch>) {
+    let schema = Generator::with_seed(SEED).with_config(config()).schema;
+    let mut csv = vec![];
+    write_csv(&mut csv, schema, batches).unwrap();
+    let checksum = format!("{:016x}\n", fnv1a(&csv));
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, &checksum).unwrap();
+        return;
+    }
+    let golden = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "failed to read golden file {}: {}, rerun with UPDATE_GOLDEN=1 to record it",
+            path.display(),
+            e
+        )
+    });
+    assert_eq!(
+        checksum, golden,
+        "output for seed {} changed, rerun with UPDATE_GOLDEN=1 if intended",
+        SEED
+    );
+}
+
+#[test]
+fn sequential_output_is_reproducible() {
+    let batches = Generator::with_seed(SEED)
+        .with_config(config())
+        .take(NUM_BATCHES);
+    check_golden("sequential.fnv1a", batches);
+}
+
+#[test]
+fn parallel_output_is_reproducible() {
+    let batches = Generator::with_seed(SEED)
+        .with_config(config())
+        .par_chunks(NUM_BATCHES);
+    check_golden("parallel.fnv1a", batches);
+}
+
+#[test]
+fn hosts_are_independent() {
+    let sequential: Vec<_> = Generator::with_seed(SEED)
+        .with_config(config())
+        .take(NUM_BATCHES)
+        .collect();
+    let parallel: Vec<_> = Generator::with_seed(SEED)
+        .with_config(config())
+        .par_chunks(NUM_BATCHES)
+        .collect();
+    assert_eq!(sequential, parallel);
+
+    // The modes can be mixed, as a host only depends on its index
+    let mut generator = Generator::with_seed(SEED).with_config(config());
+    generator.par_batches(NUM_BATCHES - 1);
+    assert_eq!(generator.next().as_ref(), sequential.last());
+}